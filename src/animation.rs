@@ -0,0 +1,66 @@
+//! Custom characters cycled through frames, see [`Animation`]
+
+use embedded_hal_async::delay::DelayNs;
+
+use crate::{CgramSlot, Error, LcdI2c, PortExpander};
+
+/// Cycles a custom character through `frames` at one cell, e.g. a spinner.
+/// Every step reprograms a single CGRAM slot, so an animation costs one of
+/// the 8 slots however many frames it has. Other cells showing the same slot
+/// animate along with it; slots used by `create_char` elsewhere must not
+/// overlap.
+pub struct Animation<'a> {
+    frames: &'a [[u8; 8]],
+    slot: CgramSlot,
+    col: u8,
+    row: u8,
+    frame: usize,
+}
+
+impl<'a> Animation<'a> {
+    /// Animates the cell at (`col`, `row`) through `slot`. The built-in
+    /// glyphs take slots of their own: `write_big_digit` uses 0-2, the full
+    /// block of `progress_bar` and `write_big_digit` 3, and the partial cells
+    /// of `progress_bar` 4-7. Sharing one of them with those helpers makes
+    /// each side overwrite the other's glyph.
+    pub fn new(frames: &'a [[u8; 8]], slot: CgramSlot, col: u8, row: u8) -> Self {
+        Self {
+            frames,
+            slot,
+            col,
+            row,
+            frame: 0,
+        }
+    }
+
+    /// CGRAM slot the frames are programmed into
+    pub fn slot(&self) -> CgramSlot {
+        self.slot
+    }
+
+    /// Index of the frame the next `step` shows
+    pub fn frame(&self) -> usize {
+        self.frame
+    }
+
+    /// Shows the next frame, wrapping after the last one. The cursor is left
+    /// just past the animated cell.
+    pub async fn step<EXP, D, E>(&mut self, lcd: &mut LcdI2c<EXP, D>) -> Result<(), Error<E>>
+    where
+        EXP: PortExpander<Error = E>,
+        D: DelayNs,
+    {
+        let Some(charmap) = self.frames.get(self.frame) else {
+            return Ok(());
+        };
+
+        // `set_cgram` leaves the address counter in CGRAM; the one
+        // `set_cursor` below takes it back to the DDRAM
+        lcd.set_cgram(self.slot, charmap).await?;
+        lcd.set_cursor(self.col, self.row).await?;
+        lcd.write_custom(self.slot).await?;
+
+        self.frame = (self.frame + 1) % self.frames.len();
+        Ok(())
+    }
+}
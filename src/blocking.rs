@@ -0,0 +1,334 @@
+//! Blocking wrapper around the async driver, see [`LcdI2cBlocking`]
+
+#[cfg(feature = "fmt")]
+use core::fmt;
+use core::future::Future;
+use core::pin::pin;
+use core::task::{Context, Poll, Waker};
+
+use embedded_hal::delay::DelayNs;
+
+#[cfg(feature = "buffered")]
+use crate::FrameBuffer;
+
+use crate::{
+    Animation, CgramSlot, Dimensions, Direction, Error, LcdConfig, LcdI2c, Pcf8574, PortExpander,
+    TimingMode,
+};
+
+// Adapts a blocking delay to the async interface used by `LcdI2c`. Its
+// futures complete on the first poll.
+struct BlockingDelay<D>(D);
+
+impl<D: DelayNs> embedded_hal_async::delay::DelayNs for BlockingDelay<D> {
+    async fn delay_ns(&mut self, ns: u32) {
+        self.0.delay_ns(ns);
+    }
+
+    async fn delay_us(&mut self, us: u32) {
+        self.0.delay_us(us);
+    }
+
+    async fn delay_ms(&mut self, ms: u32) {
+        self.0.delay_ms(ms);
+    }
+}
+
+// With a blocking expander and delay nothing ever returns `Pending`, so a
+// single poll drives the future to completion. Anything else (an `AsyncI2c`
+// bus) is busy-polled until it finishes.
+pub(crate) fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = pin!(future);
+    let mut cx = Context::from_waker(Waker::noop());
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+            return output;
+        }
+    }
+}
+
+/// Blocking [`detect`](crate::detect)
+pub fn detect<I2C: embedded_hal::i2c::I2c>(i2c: &mut I2C, addresses: &[u8]) -> Option<u8> {
+    block_on(crate::detect(i2c, addresses))
+}
+
+macro_rules! blocking {
+    ($(fn $name:ident(&mut self $(, $arg:ident: $ty:ty)*) -> $ret:ty;)*) => {
+        $(
+            #[doc = concat!("Blocking [`LcdI2c::", stringify!($name), "`]")]
+            pub fn $name(&mut self $(, $arg: $ty)*) -> $ret {
+                block_on(self.inner.$name($($arg),*))
+            }
+        )*
+    };
+}
+
+/// [`LcdI2c`] for a blocking bus and delay. Every async method has a
+/// blocking counterpart of the same name, which runs it to completion.
+pub struct LcdI2cBlocking<EXP, D> {
+    inner: LcdI2c<EXP, BlockingDelay<D>>,
+}
+
+impl<EXP, D, E> LcdI2cBlocking<EXP, D>
+where
+    EXP: PortExpander<Error = E>,
+    D: DelayNs,
+{
+    /// Like [`LcdI2c::new`]
+    pub fn new(expander: EXP, delay: D) -> Self {
+        Self::new_with_size(expander, Dimensions::default(), delay)
+    }
+
+    /// Like [`LcdI2c::new_with_size`]
+    pub fn new_with_size(expander: EXP, dimensions: Dimensions, delay: D) -> Self {
+        Self {
+            inner: LcdI2c::new_with_size(expander, dimensions, BlockingDelay(delay)),
+        }
+    }
+
+    /// Like [`LcdI2c::new_with_config`]
+    pub fn new_with_config(expander: EXP, config: LcdConfig, delay: D) -> Result<Self, Error<E>> {
+        Ok(Self {
+            inner: LcdI2c::new_with_config(expander, config, BlockingDelay(delay))?,
+        })
+    }
+
+    /// Gives the expander and delay back
+    pub fn release(self) -> (EXP, D) {
+        let (expander, delay) = self.inner.release();
+        (expander, delay.0)
+    }
+
+    /// Like [`LcdI2c::expander`]
+    pub fn expander(&self) -> &EXP {
+        self.inner.expander()
+    }
+
+    /// Like [`LcdI2c::expander_mut`]
+    pub fn expander_mut(&mut self) -> &mut EXP {
+        self.inner.expander_mut()
+    }
+
+    blocking! {
+        fn begin(&mut self) -> Result<(), Error<E>>;
+        fn begin_with_retries(&mut self, attempts: u8) -> Result<(), Error<E>>;
+        fn reset(&mut self) -> Result<(), Error<E>>;
+        fn resync_4bit(&mut self) -> Result<(), Error<E>>;
+        fn suspend(&mut self) -> Result<(), Error<E>>;
+        fn resume(&mut self) -> Result<(), Error<E>>;
+        fn ping(&mut self) -> Result<(), Error<E>>;
+        fn reconnect(&mut self) -> Result<(), Error<E>>;
+        fn clear(&mut self) -> Result<(), Error<E>>;
+        fn home(&mut self) -> Result<(), Error<E>>;
+        fn display(&mut self) -> Result<(), Error<E>>;
+        fn no_display(&mut self) -> Result<(), Error<E>>;
+        fn cursor(&mut self) -> Result<(), Error<E>>;
+        fn no_cursor(&mut self) -> Result<(), Error<E>>;
+        fn blink(&mut self) -> Result<(), Error<E>>;
+        fn no_blink(&mut self) -> Result<(), Error<E>>;
+        fn left_to_right(&mut self) -> Result<(), Error<E>>;
+        fn right_to_left(&mut self) -> Result<(), Error<E>>;
+        fn autoscroll(&mut self) -> Result<(), Error<E>>;
+        fn no_autoscroll(&mut self) -> Result<(), Error<E>>;
+        fn scroll_display_left(&mut self) -> Result<(), Error<E>>;
+        fn scroll_display_right(&mut self) -> Result<(), Error<E>>;
+        fn scroll_display(&mut self, amount: i8) -> Result<(), Error<E>>;
+        fn reset_scroll(&mut self) -> Result<(), Error<E>>;
+        fn move_cursor_left(&mut self) -> Result<(), Error<E>>;
+        fn move_cursor_right(&mut self) -> Result<(), Error<E>>;
+        fn set_cursor(&mut self, col: u8, row: u8) -> Result<(), Error<E>>;
+        fn set_cgram(&mut self, slot: CgramSlot, charmap: &[u8; 8]) -> Result<(), Error<E>>;
+        fn create_char(&mut self, slot: CgramSlot, charmap: &[u8; 8]) -> Result<(), Error<E>>;
+        fn command(&mut self, byte: u8) -> Result<(), Error<E>>;
+        fn data(&mut self, byte: u8) -> Result<(), Error<E>>;
+        fn write_custom(&mut self, slot: CgramSlot) -> Result<(), Error<E>>;
+        fn write_byte(&mut self, byte: u8) -> Result<(), Error<E>>;
+        fn write_byte_fast(&mut self, byte: u8) -> Result<(), Error<E>>;
+        fn write_str(&mut self, s: &str) -> Result<(), Error<E>>;
+        fn write_char(&mut self, c: char) -> Result<(), Error<E>>;
+        fn write_big_digit(&mut self, digit: u8, col: u8, row: u8) -> Result<(), Error<E>>;
+        fn progress_bar(&mut self, col: u8, row: u8, width: u8, percent: u8) -> Result<(), Error<E>>;
+        fn write_repeated(&mut self, byte: u8, count: usize) -> Result<(), Error<E>>;
+        fn write_bytes(&mut self, data: &[u8]) -> Result<(), Error<E>>;
+        fn write_u32(&mut self, value: u32) -> Result<(), Error<E>>;
+        fn write_u32_padded(&mut self, value: u32, width: u8, fill: u8) -> Result<(), Error<E>>;
+        fn write_i32(&mut self, value: i32) -> Result<(), Error<E>>;
+        fn clear_line(&mut self, row: u8) -> Result<(), Error<E>>;
+        fn write_wrapped(&mut self, s: &str) -> Result<(), Error<E>>;
+        fn scroll_text_step(&mut self, text: &str, row: u8, offset: usize) -> Result<(), Error<E>>;
+        fn write_screen(&mut self, lines: &[&str]) -> Result<(), Error<E>>;
+        fn write_field(&mut self, col: u8, row: u8, s: &str, width: u8) -> Result<(), Error<E>>;
+        fn write_str_clipped(&mut self, s: &str) -> Result<usize, Error<E>>;
+        fn write_str_at(&mut self, col: u8, row: u8, s: &str) -> Result<usize, Error<E>>;
+        fn commit(&mut self) -> Result<(), Error<E>>;
+        fn is_ready(&mut self) -> Result<bool, Error<E>>;
+        fn read_address(&mut self) -> Result<u8, Error<E>>;
+        fn read_byte(&mut self) -> Result<u8, Error<E>>;
+        fn backlight(&mut self) -> Result<(), Error<E>>;
+        fn no_backlight(&mut self) -> Result<(), Error<E>>;
+        fn set_display(&mut self, on: bool) -> Result<(), Error<E>>;
+        fn set_cursor_visible(&mut self, visible: bool) -> Result<(), Error<E>>;
+        fn set_blink(&mut self, on: bool) -> Result<(), Error<E>>;
+        fn set_autoscroll(&mut self, on: bool) -> Result<(), Error<E>>;
+        fn blink_at(&mut self, col: u8, row: u8) -> Result<(), Error<E>>;
+        fn blink_tick(&mut self) -> Result<(), Error<E>>;
+        fn stop_editing(&mut self) -> Result<(), Error<E>>;
+        fn set_display_control(&mut self, display: bool, cursor: bool, blink: bool) -> Result<(), Error<E>>;
+        fn set_text_direction(&mut self, direction: Direction) -> Result<(), Error<E>>;
+        fn set_entry_mode(&mut self, direction: Direction, autoscroll: bool) -> Result<(), Error<E>>;
+        fn set_backlight(&mut self, on: bool) -> Result<(), Error<E>>;
+        fn set_backlight_level(&mut self, level: u8) -> Result<(), Error<E>>;
+    }
+
+    /// Like [`LcdI2c::use_busy_flag`]
+    pub fn use_busy_flag(&mut self, enabled: bool) {
+        self.inner.use_busy_flag(enabled);
+    }
+
+    /// Blocking `LcdI2c::update_line`
+    #[cfg(feature = "buffered")]
+    pub fn update_line(&mut self, row: u8, s: &str) -> Result<bool, Error<E>> {
+        block_on(self.inner.update_line(row, s))
+    }
+
+    /// Blocking [`Animation::step`], which needs the wrapped driver
+    pub fn step_animation(&mut self, animation: &mut Animation) -> Result<(), Error<E>> {
+        block_on(animation.step(&mut self.inner))
+    }
+
+    /// Blocking [`FrameBuffer::flush`], which needs the wrapped driver
+    #[cfg(feature = "buffered")]
+    pub fn flush_frame(&mut self, frame: &mut FrameBuffer) -> Result<(), Error<E>> {
+        block_on(frame.flush(&mut self.inner))
+    }
+
+    /// Like [`LcdI2c::busy_timeouts`]
+    pub fn busy_timeouts(&self) -> u32 {
+        self.inner.busy_timeouts()
+    }
+
+    /// Like `LcdI2c::transactions`
+    #[cfg(feature = "stats")]
+    pub fn transactions(&self) -> u32 {
+        self.inner.transactions()
+    }
+
+    /// Like `LcdI2c::reset_transactions`
+    #[cfg(feature = "stats")]
+    pub fn reset_transactions(&mut self) {
+        self.inner.reset_transactions();
+    }
+
+    /// Like [`LcdI2c::set_rs`]
+    pub fn set_rs(&mut self, high: bool) {
+        self.inner.set_rs(high);
+    }
+
+    /// Like [`LcdI2c::set_rw`]
+    pub fn set_rw(&mut self, high: bool) {
+        self.inner.set_rw(high);
+    }
+
+    /// Like [`LcdI2c::set_enable`]
+    pub fn set_enable(&mut self, high: bool) {
+        self.inner.set_enable(high);
+    }
+
+    /// Like [`LcdI2c::set_data`]
+    pub fn set_data(&mut self, nibble: u8) {
+        self.inner.set_data(nibble);
+    }
+
+    /// Like [`LcdI2c::set_timing_mode`]
+    pub fn set_timing_mode(&mut self, mode: TimingMode) {
+        self.inner.set_timing_mode(mode);
+    }
+
+    /// Like [`LcdI2c::timing_mode`]
+    pub fn timing_mode(&self) -> TimingMode {
+        self.inner.timing_mode()
+    }
+
+    /// Like [`LcdI2c::dimensions`]
+    pub fn dimensions(&self) -> Dimensions {
+        self.inner.dimensions()
+    }
+
+    /// Like [`LcdI2c::is_connected`]
+    pub fn is_connected(&self) -> bool {
+        self.inner.is_connected()
+    }
+
+    /// Like [`LcdI2c::scroll_offset`]
+    pub fn scroll_offset(&self) -> u8 {
+        self.inner.scroll_offset()
+    }
+
+    /// Like [`LcdI2c::position`]
+    pub fn position(&self) -> (u8, u8) {
+        self.inner.position()
+    }
+
+    /// Like [`LcdI2c::is_display_on`]
+    pub fn is_display_on(&self) -> bool {
+        self.inner.is_display_on()
+    }
+
+    /// Like [`LcdI2c::is_cursor_on`]
+    pub fn is_cursor_on(&self) -> bool {
+        self.inner.is_cursor_on()
+    }
+
+    /// Like [`LcdI2c::is_blink_on`]
+    pub fn is_blink_on(&self) -> bool {
+        self.inner.is_blink_on()
+    }
+
+    /// Like [`LcdI2c::is_autoscroll`]
+    pub fn is_autoscroll(&self) -> bool {
+        self.inner.is_autoscroll()
+    }
+
+    /// Like [`LcdI2c::text_direction`]
+    pub fn text_direction(&self) -> Direction {
+        self.inner.text_direction()
+    }
+}
+
+/// Shortcuts for the usual PCF8574 backpack at 0x27
+impl<I2C, D> LcdI2cBlocking<Pcf8574<I2C>, D>
+where
+    I2C: embedded_hal::i2c::I2c,
+    D: DelayNs,
+{
+    /// Like [`LcdI2c::default_16x2`]
+    pub fn default_16x2(i2c: I2C, delay: D) -> Self {
+        Self::new(Pcf8574::new(i2c, 0x27), delay)
+    }
+
+    /// Like [`LcdI2c::default_20x4`]
+    pub fn default_20x4(i2c: I2C, delay: D) -> Self {
+        Self::new_with_size(Pcf8574::new(i2c, 0x27), Dimensions::new(20, 4), delay)
+    }
+}
+
+impl<EXP, D, E> core::fmt::Debug for LcdI2cBlocking<EXP, D>
+where
+    EXP: PortExpander<Error = E>,
+    D: DelayNs,
+{
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        self.inner.fmt(f)
+    }
+}
+
+#[cfg(feature = "fmt")]
+impl<EXP, D, E> fmt::Write for LcdI2cBlocking<EXP, D>
+where
+    EXP: PortExpander<Error = E>,
+    D: DelayNs,
+{
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        fmt::Write::write_str(&mut self.inner, s)
+    }
+}
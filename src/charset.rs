@@ -0,0 +1,181 @@
+//! Character ROMs and CGRAM glyphs: [`RomVariant`], [`CgramSlot`] and
+//! the [`charmap`] helpers
+
+/// Character generator ROM fitted to the controller. The part number suffix
+/// (HD44780UA00, HD44780UA02) tells them apart.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RomVariant {
+    /// Japanese: ASCII (with ¥, → and ← at 0x5C, 0x7E and 0x7F), katakana at
+    /// 0xA1-0xDF and a handful of Greek letters and symbols above that
+    #[default]
+    A00,
+    /// European: ASCII plus Cyrillic/Greek at 0x80-0x9F and a Latin-1 layout
+    /// at 0xA0-0xFF
+    A02,
+}
+
+/// One of the 8 CGRAM slots for custom glyphs, which print as character
+/// codes 0-7
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CgramSlot(u8);
+
+impl CgramSlot {
+    /// Every slot, indexed by its character code
+    pub const ALL: [Self; 8] = [
+        Self(0),
+        Self(1),
+        Self(2),
+        Self(3),
+        Self(4),
+        Self(5),
+        Self(6),
+        Self(7),
+    ];
+
+    /// `None` past slot 7
+    pub const fn new(index: u8) -> Option<Self> {
+        if index < 8 { Some(Self(index)) } else { None }
+    }
+
+    /// Also the character code that prints the glyph
+    pub const fn index(self) -> u8 {
+        self.0
+    }
+}
+
+/// Builds a glyph for `create_char` from 8 rows of 5 pixels, '#' for on and
+/// ' ' or '.' for off. Anything else panics, which in a `const` is a compile
+/// error:
+///
+/// ```
+/// use lcd_i2c::charmap;
+///
+/// const HEART: [u8; 8] = charmap([
+///     ".....", ".#.#.", "#####", "#####", ".###.", "..#..", ".....", ".....",
+/// ]);
+/// assert_eq!(HEART[2], 0x1F);
+/// ```
+pub const fn charmap(rows: [&str; 8]) -> [u8; 8] {
+    let mut glyph = [0; 8];
+    let mut row = 0;
+    while row < 8 {
+        let pixels = rows[row].as_bytes();
+        assert!(pixels.len() == 5, "charmap rows are 5 pixels wide");
+        let mut col = 0;
+        while col < 5 {
+            glyph[row] <<= 1;
+            match pixels[col] {
+                b'#' => glyph[row] |= 1,
+                b' ' | b'.' => {}
+                _ => panic!("charmap pixels are '#', ' ' or '.'"),
+            }
+            col += 1;
+        }
+        row += 1;
+    }
+    glyph
+}
+
+/// Checks that a glyph written as numbers (e.g. binary literals) only uses
+/// the 5 displayed bits of each row
+pub const fn charmap_bits(rows: [u8; 8]) -> [u8; 8] {
+    let mut row = 0;
+    while row < 8 {
+        assert!(rows[row] <= 0x1F, "charmap rows only have 5 bits");
+        row += 1;
+    }
+    rows
+}
+
+// Glyphs for `write_big_digit`: a bar along the top, a bar along the
+// bottom, and both bars. Full cells come from the ROM where it has one.
+pub(crate) const BIG_DIGIT_GLYPHS: [[u8; 8]; 3] = [
+    [0x1F, 0x1F, 0x1F, 0x00, 0x00, 0x00, 0x00, 0x00],
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x1F, 0x1F, 0x1F],
+    [0x1F, 0x1F, 0x1F, 0x00, 0x00, 0x1F, 0x1F, 0x1F],
+];
+
+// Stands for the full block in `BIG_DIGITS`
+pub(crate) const F: u8 = 0xFF;
+const T: u8 = 0;
+const B: u8 = 1;
+const TB: u8 = 2;
+const SP: u8 = b' ';
+
+// Top and bottom row of each digit, as codes of the glyphs above
+pub(crate) const BIG_DIGITS: [[[u8; 3]; 2]; 10] = [
+    [[F, T, F], [F, B, F]],
+    [[T, F, SP], [B, F, B]],
+    [[TB, TB, F], [F, B, B]],
+    [[TB, TB, F], [B, B, F]],
+    [[F, B, F], [SP, SP, F]],
+    [[F, TB, TB], [B, B, F]],
+    [[F, TB, TB], [F, B, F]],
+    [[T, T, F], [SP, SP, F]],
+    [[F, TB, F], [F, B, F]],
+    [[F, TB, F], [B, B, F]],
+];
+
+// Code used for characters the ROM cannot show
+const UNMAPPED: u8 = b'?';
+
+impl RomVariant {
+    /// Display code for `c`, or `?` for characters this ROM cannot show
+    pub fn encode(self, c: char) -> u8 {
+        match self {
+            Self::A00 => encode_a00(c),
+            Self::A02 => encode_a02(c),
+        }
+    }
+
+    // Code of a solid 5x8 block, which A00 has at 0xFF. A02 has ÿ there.
+    pub(crate) fn full_block(self) -> Option<u8> {
+        match self.encode('█') {
+            UNMAPPED => None,
+            code => Some(code),
+        }
+    }
+}
+
+fn encode_a00(c: char) -> u8 {
+    match c {
+        // 0x5C and 0x7E hold ¥ and → instead of '\' and '~'
+        '\\' | '~' => UNMAPPED,
+        ' '..='}' => c as u8,
+        '¥' => 0x5C,
+        '→' => 0x7E,
+        '←' => 0x7F,
+        // Half-width katakana are stored in Unicode order
+        '\u{FF61}'..='\u{FF9F}' => (c as u32 - 0xFF61 + 0xA1) as u8,
+        '°' => 0xDF,
+        'α' => 0xE0,
+        'ä' => 0xE1,
+        'β' => 0xE2,
+        'ε' => 0xE3,
+        'µ' | 'μ' => 0xE4,
+        'σ' => 0xE5,
+        'ρ' => 0xE6,
+        '√' => 0xE8,
+        '¢' => 0xEC,
+        '£' => 0xED,
+        'ñ' => 0xEE,
+        'ö' => 0xEF,
+        'θ' => 0xF2,
+        '∞' => 0xF3,
+        'Ω' => 0xF4,
+        'ü' => 0xF5,
+        'Σ' => 0xF6,
+        'π' => 0xF7,
+        '÷' => 0xFD,
+        '█' => 0xFF,
+        _ => UNMAPPED,
+    }
+}
+
+fn encode_a02(c: char) -> u8 {
+    match c {
+        ' '..='~' => c as u8,
+        '\u{A0}'..='\u{FF}' => c as u8,
+        _ => UNMAPPED,
+    }
+}
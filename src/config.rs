@@ -0,0 +1,394 @@
+//! Display settings, see [`LcdConfig`]
+
+use crate::RomVariant;
+
+/// Visible size of the display in characters
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Dimensions {
+    /// Characters per row
+    pub cols: u8,
+    /// Rows, 1-4
+    pub rows: u8,
+}
+
+impl Dimensions {
+    /// `cols` x `rows` characters
+    pub const fn new(cols: u8, rows: u8) -> Self {
+        Self { cols, rows }
+    }
+
+    // DDRAM address of the first column of each row. Rows 3 and 4 continue
+    // rows 1 and 2 respectively, so their offsets depend on the column count
+    // (0x14/0x54 on a 20x4, 0x10/0x50 on a 16x4).
+    // Only meaningful for geometries accepted by `LcdConfig::is_valid`
+    pub(crate) fn row_offsets(&self) -> [u8; 4] {
+        [0x00, 0x40, self.cols, self.cols.wrapping_add(0x40)]
+    }
+
+    // Bytes of DDRAM the visible cells take
+    pub(crate) const fn cells(&self) -> u16 {
+        self.cols as u16 * self.rows as u16
+    }
+}
+
+impl Default for Dimensions {
+    fn default() -> Self {
+        Self::new(16, 2)
+    }
+}
+
+/// Character cell size selected by the function set instruction
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Font {
+    /// The usual 5x8 dot cells
+    Dots5x8,
+    /// Only available in one-line mode
+    Dots5x10,
+}
+
+/// Display lines as the controller sees them, which decides the DDRAM layout
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Lines {
+    /// A single row of 80 characters of DDRAM
+    One,
+    /// Also used by many 16x1 modules, which are wired as two 8 character lines
+    Two,
+}
+
+/// Controller family, for the differences in initialization and timing
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Controller {
+    /// Hitachi HD44780 and compatibles (KS0066, ST7066U, ...)
+    Hd44780,
+    /// Winstar character OLEDs: own reset sequence, character mode select,
+    /// no backlight and no 5x10 font
+    Ws0010,
+}
+
+impl Controller {
+    // Execution time of clear and return home
+    pub(crate) fn clear_us(self) -> u32 {
+        match self {
+            Self::Hd44780 => 1600,
+            Self::Ws0010 => 6200,
+        }
+    }
+}
+
+/// Width of the data bus between the expander and the LCD
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Interface {
+    /// D4-D7 only, each byte sent as two nibbles
+    FourBit,
+    /// D0-D7 on a second port of the expander, see `PortExpander::has_data_port`
+    EightBit,
+}
+
+/// How the driver waits for an instruction to finish
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TimingMode {
+    /// Worst-case execution times from the datasheet; works write-only
+    FixedDelay,
+    /// Poll the busy flag, which needs readable RW and data lines
+    Busy,
+}
+
+/// Whether RW is wired to the expander. Many backpacks tie it to ground,
+/// making the busy flag and every read unavailable.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReadCapability {
+    /// RW is on an expander pin, so the busy flag and DDRAM can be read
+    ReadWrite,
+    /// RW is tied to ground
+    WriteOnly,
+}
+
+/// Direction the cursor moves after each character
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    /// Address counter increments; text reads left to right
+    LeftToRight,
+    /// Address counter decrements; text is written leftwards
+    RightToLeft,
+}
+
+/// Settings for [`LcdI2c::new_with_config`](crate::LcdI2c::new_with_config),
+/// built from [`LcdConfig::new`] with `const` setters
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LcdConfig {
+    pub(crate) dimensions: Dimensions,
+    pub(crate) lines: Lines,
+    pub(crate) font: Font,
+    pub(crate) backlight: bool,
+    pub(crate) backlight_active_low: bool,
+    pub(crate) cursor: bool,
+    pub(crate) blink: bool,
+    pub(crate) mask_charmap: bool,
+    pub(crate) preserve_cursor: bool,
+    pub(crate) rom: RomVariant,
+    pub(crate) batch_writes: bool,
+    pub(crate) enable_pulse_us: u32,
+    pub(crate) setup_us: u32,
+    pub(crate) nibble_settle_us: u32,
+    pub(crate) disconnect_after: u8,
+    pub(crate) power_on_delay_ms: u32,
+    pub(crate) row_offsets: Option<[u8; 4]>,
+    pub(crate) busy_poll_limit: u16,
+    pub(crate) interface: Interface,
+    pub(crate) controller: Controller,
+    pub(crate) read_capability: ReadCapability,
+    pub(crate) verify_init: bool,
+    pub(crate) fmt_char_delay_us: u32,
+}
+
+impl LcdConfig {
+    /// A 16x2 HD44780 on the 4-bit interface, with the backlight, cursor and
+    /// blink off
+    pub const fn new() -> Self {
+        Self {
+            dimensions: Dimensions::new(16, 2),
+            lines: Lines::Two,
+            font: Font::Dots5x8,
+            backlight: false,
+            backlight_active_low: false,
+            cursor: false,
+            blink: false,
+            mask_charmap: false,
+            preserve_cursor: false,
+            rom: RomVariant::A00,
+            batch_writes: false,
+            enable_pulse_us: 1,
+            setup_us: 0,
+            nibble_settle_us: 37,
+            disconnect_after: 0,
+            power_on_delay_ms: 50,
+            row_offsets: None,
+            busy_poll_limit: 100,
+            interface: Interface::FourBit,
+            controller: Controller::Hd44780,
+            read_capability: ReadCapability::ReadWrite,
+            verify_init: false,
+            fmt_char_delay_us: 0,
+        }
+    }
+
+    /// Visible size of the display
+    pub const fn dimensions(mut self, dimensions: Dimensions) -> Self {
+        self.dimensions = dimensions;
+        self
+    }
+
+    /// Characters per row, keeping the row count
+    pub const fn cols(mut self, cols: u8) -> Self {
+        self.dimensions.cols = cols;
+        self
+    }
+
+    /// Number of rows, keeping the column count. Single-row modules still
+    /// need `lines(Lines::One)` for one-line mode.
+    pub const fn rows(mut self, rows: u8) -> Self {
+        self.dimensions.rows = rows;
+        self
+    }
+
+    /// Controller line mode sent in the function set instruction
+    pub const fn lines(mut self, lines: Lines) -> Self {
+        self.lines = lines;
+        self
+    }
+
+    /// Character cell size sent in the function set instruction
+    pub const fn font(mut self, font: Font) -> Self {
+        self.font = font;
+        self
+    }
+
+    /// Shorthand for `font(Font::Dots5x10)` or `font(Font::Dots5x8)`
+    pub const fn font_5x10(self, enabled: bool) -> Self {
+        self.font(if enabled {
+            Font::Dots5x10
+        } else {
+            Font::Dots5x8
+        })
+    }
+
+    /// Applied by `begin`
+    pub const fn backlight(mut self, on: bool) -> Self {
+        self.backlight = on;
+        self
+    }
+
+    /// For backpacks that switch the backlight through an inverting
+    /// transistor, where a set LED bit turns it off
+    pub const fn backlight_active_low(mut self, active_low: bool) -> Self {
+        self.backlight_active_low = active_low;
+        self
+    }
+
+    /// Selects the initialization sequence and instruction timings
+    pub const fn controller(mut self, controller: Controller) -> Self {
+        self.controller = controller;
+        self
+    }
+
+    /// With `WriteOnly` reads fail with `Error::Unsupported` and the busy flag
+    /// is never used, whatever `use_busy_flag` asks for
+    pub const fn read_capability(mut self, capability: ReadCapability) -> Self {
+        self.read_capability = capability;
+        self
+    }
+
+    /// Have `begin` poll the busy flag after initialization (up to
+    /// `busy_poll_limit` times) and fail with `Error::InitFailed` if the
+    /// display never reports ready. Skipped for write-only wiring.
+    pub const fn verify_init(mut self, enabled: bool) -> Self {
+        self.verify_init = enabled;
+        self
+    }
+
+    /// Width of the data bus; `EightBit` needs an expander with a data port
+    pub const fn interface(mut self, interface: Interface) -> Self {
+        self.interface = interface;
+        self
+    }
+
+    /// Applied by `begin`
+    pub const fn cursor(mut self, visible: bool) -> Self {
+        self.cursor = visible;
+        self
+    }
+
+    /// Applied by `begin`
+    pub const fn blink(mut self, on: bool) -> Self {
+        self.blink = on;
+        self
+    }
+
+    /// Selects how `write_str` maps characters to display codes
+    pub const fn rom(mut self, rom: RomVariant) -> Self {
+        self.rom = rom;
+        self
+    }
+
+    /// Hand the four port states of each byte to the expander at once. The
+    /// PCF8574 sends them as one I2C transaction and the MCP23S08 as one SPI
+    /// transfer; the TCA9534 and PCF8575 have no way to latch several states
+    /// in one write and still send four. The E pulse then lasts one byte time
+    /// on the bus, which is plenty for the HD44780 but worth checking on a
+    /// very fast bus with a slow clone.
+    pub const fn batch_writes(mut self, enabled: bool) -> Self {
+        self.batch_writes = enabled;
+        self
+    }
+
+    /// How long E is held high for each nibble. Slow clones may need more
+    /// than the default 1us. Batched writes are timed by the bus instead.
+    pub const fn enable_pulse_us(mut self, us: u32) -> Self {
+        self.enable_pulse_us = us;
+        self
+    }
+
+    /// Wait between the high and low nibble of each 4-bit transfer. Long
+    /// cables or level shifters may need more than the default 37us.
+    pub const fn nibble_settle_us(mut self, us: u32) -> Self {
+        self.nibble_settle_us = us;
+        self
+    }
+
+    /// Delay between setting RS/RW and raising E. Zero (the default) raises E
+    /// in the same port write.
+    pub const fn setup_us(mut self, us: u32) -> Self {
+        self.setup_us = us;
+        self
+    }
+
+    /// After this many consecutive bus errors the driver treats the display
+    /// as absent and returns `Error::Disconnected` without touching the bus.
+    /// Zero (the default) never disconnects.
+    pub const fn disconnect_after(mut self, failures: u8) -> Self {
+        self.disconnect_after = failures;
+        self
+    }
+
+    /// DDRAM address of the first column of each row, replacing the layout
+    /// derived from the dimensions for modules wired differently. A 16x1
+    /// module split as 8+8 is driven as 8x2 with the standard 0x00/0x40.
+    pub const fn row_offsets(mut self, offsets: [u8; 4]) -> Self {
+        self.row_offsets = Some(offsets);
+        self
+    }
+
+    /// Wait before `begin` (and `reset`) touch the bus, for supplies that are
+    /// still rising when the MCU starts. The HD44780 needs 40ms after VCC
+    /// reaches 2.7V; the default 50ms leaves some margin.
+    pub const fn power_on_delay_ms(mut self, ms: u32) -> Self {
+        self.power_on_delay_ms = ms;
+        self
+    }
+
+    /// Busy-flag reads after an instruction before giving up with
+    /// `Error::Timeout`. Each read takes a few bus transactions, so the
+    /// default 100 is several milliseconds even on a fast bus.
+    pub const fn busy_poll_limit(mut self, reads: u16) -> Self {
+        self.busy_poll_limit = reads;
+        self
+    }
+
+    /// Extra wait after each character written through `fmt::Write`, for
+    /// clones slower than the 41us (or the busy flag) every write already
+    /// waits for
+    pub const fn fmt_char_delay_us(mut self, us: u32) -> Self {
+        self.fmt_char_delay_us = us;
+        self
+    }
+
+    /// Clear the top 3 bits of every `create_char` row, which the HD44780
+    /// ignores anyway
+    pub const fn mask_charmap(mut self, enabled: bool) -> Self {
+        self.mask_charmap = enabled;
+        self
+    }
+
+    /// Have `create_char` return to the DDRAM address it started from rather
+    /// than (0, 0). The address is read back over RW when busy-flag polling is
+    /// enabled, and taken from the driver's own tracking otherwise.
+    pub const fn preserve_cursor(mut self, enabled: bool) -> Self {
+        self.preserve_cursor = enabled;
+        self
+    }
+
+    /// 1-4 rows of at least one column, fitting the 160 bytes of DDRAM of a
+    /// dual-controller display (`new_with_config` holds single-controller
+    /// displays to 80), a single row in one-line mode and no 5x10 font in
+    /// two-line mode
+    pub const fn is_valid(&self) -> bool {
+        let Dimensions { cols, rows } = self.dimensions;
+        let geometry = cols > 0 && rows > 0 && rows <= 4 && self.dimensions.cells() <= 160;
+        let lines = match self.lines {
+            Lines::One => rows == 1,
+            Lines::Two => !matches!(self.font, Font::Dots5x10),
+        };
+        geometry && lines
+    }
+
+    // Function set instruction (DL=0 for the 4-bit interface)
+    pub(crate) fn function_set(&self) -> u8 {
+        let mut function = 0x20;
+        if let Interface::EightBit = self.interface {
+            function |= 1 << 4;
+        }
+        if let Lines::Two = self.lines {
+            function |= 1 << 3;
+        }
+        if let (Font::Dots5x10, Controller::Hd44780) = (self.font, self.controller) {
+            function |= 1 << 2;
+        }
+        function
+    }
+}
+
+impl Default for LcdConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,15 @@
+//! Delay implementations, see [`NoDelay`]
+
+/// Delay that returns immediately, for driving the LCD against a mock bus in
+/// host-side tests or a simulator. Works with both `LcdI2c` and
+/// `LcdI2cBlocking`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NoDelay;
+
+impl embedded_hal::delay::DelayNs for NoDelay {
+    fn delay_ns(&mut self, _ns: u32) {}
+}
+
+impl embedded_hal_async::delay::DelayNs for NoDelay {
+    async fn delay_ns(&mut self, _ns: u32) {}
+}
@@ -0,0 +1,840 @@
+//! The [`PortExpander`] trait, its bus traits and the supported expanders
+
+use embedded_hal::i2c::I2c;
+
+// TCA9534 registers
+const TCA9534_REG_INPUT: u8 = 0x00;
+const TCA9534_REG_OUTPUT: u8 = 0x01;
+const TCA9534_REG_POLARITY: u8 = 0x02;
+const TCA9534_REG_CONFIG: u8 = 0x03;
+
+// MCP23S08 registers
+const MCP23S08_REG_IODIR: u8 = 0x00;
+const MCP23S08_REG_IPOL: u8 = 0x01;
+const MCP23S08_REG_IOCON: u8 = 0x05;
+const MCP23S08_REG_GPIO: u8 = 0x09;
+const MCP23S08_REG_OLAT: u8 = 0x0A;
+
+/// Bit positions of the HD44780 lines on the expander port. `data` is the
+/// position of D4; D5-D7 follow on the next three bits.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PinMap {
+    /// Register select
+    pub rs: u8,
+    /// Read/write
+    pub rw: u8,
+    /// Enable, or the first controller's enable on 40x4 displays
+    pub e: u8,
+    /// Backlight
+    pub led: u8,
+    /// D4, with D5-D7 on the three bits above it
+    pub data: u8,
+    /// Enable line of the second controller on 40x4 displays, which drives
+    /// rows 2 and 3. With RW tied low its pin can be reused; the driver then
+    /// refuses reads with `Error::Unsupported` and keeps busy polling off.
+    pub e2: Option<u8>,
+}
+
+impl PinMap {
+    /// RS=P0, RW=P1, E=P2, backlight=P3, D4-D7=P4-P7
+    pub const DEFAULT: Self = Self {
+        rs: 0,
+        rw: 1,
+        e: 2,
+        led: 3,
+        data: 4,
+        e2: None,
+    };
+
+    /// Control lines on the high nibble and data on the low one, as wired on
+    /// some backpacks: D4-D7=P0-P3, RS=P4, RW=P5, E=P6, backlight=P7
+    pub const DATA_LOW: Self = Self {
+        rs: 4,
+        rw: 5,
+        e: 6,
+        led: 7,
+        data: 0,
+        e2: None,
+    };
+
+    /// Every line on its own pin of the 8-bit port, with D4-D7 fitting in
+    /// it. `e2` may share the RW pin, as described above.
+    pub const fn is_valid(&self) -> bool {
+        if self.data > 4 {
+            return false;
+        }
+        let mut used = 0x0F << self.data;
+        let pins = [self.rs, self.rw, self.e, self.led];
+        let mut i = 0;
+        while i < pins.len() {
+            if pins[i] >= 8 || used & 1 << pins[i] != 0 {
+                return false;
+            }
+            used |= 1 << pins[i];
+            i += 1;
+        }
+        match self.e2 {
+            Some(e2) if e2 == self.rw => true,
+            Some(e2) => e2 < 8 && used & 1 << e2 == 0,
+            None => true,
+        }
+    }
+}
+
+impl Default for PinMap {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// I2C transactions used by the expanders. Implemented for every blocking
+/// `embedded_hal::i2c::I2c` bus, and for `embedded_hal_async` buses wrapped
+/// in `AsyncI2c`. Shared-bus handles (`&mut I2C`, or the devices from
+/// embedded-hal-bus) implement those traits too, so several displays can sit
+/// on one bus.
+#[allow(async_fn_in_trait)]
+pub trait I2cBus {
+    /// Error of the underlying bus
+    type Error;
+
+    /// Writes `bytes` to the device at `address`
+    async fn write(&mut self, address: u8, bytes: &[u8]) -> Result<(), Self::Error>;
+
+    /// Fills `buffer` from the device at `address`
+    async fn read(&mut self, address: u8, buffer: &mut [u8]) -> Result<(), Self::Error>;
+
+    /// Writes `bytes` and reads `buffer` back with a repeated start
+    async fn write_read(
+        &mut self,
+        address: u8,
+        bytes: &[u8],
+        buffer: &mut [u8],
+    ) -> Result<(), Self::Error>;
+}
+
+impl<I2C, E> I2cBus for I2C
+where
+    I2C: I2c<Error = E>,
+{
+    type Error = E;
+
+    async fn write(&mut self, address: u8, bytes: &[u8]) -> Result<(), E> {
+        I2c::write(self, address, bytes)
+    }
+
+    async fn read(&mut self, address: u8, buffer: &mut [u8]) -> Result<(), E> {
+        I2c::read(self, address, buffer)
+    }
+
+    async fn write_read(&mut self, address: u8, bytes: &[u8], buffer: &mut [u8]) -> Result<(), E> {
+        I2c::write_read(self, address, bytes, buffer)
+    }
+}
+
+/// Non-blocking bus adapter, so transactions yield to the executor
+pub struct AsyncI2c<I2C>(pub I2C);
+
+impl<I2C, E> I2cBus for AsyncI2c<I2C>
+where
+    I2C: embedded_hal_async::i2c::I2c<Error = E>,
+{
+    type Error = E;
+
+    async fn write(&mut self, address: u8, bytes: &[u8]) -> Result<(), E> {
+        self.0.write(address, bytes).await
+    }
+
+    async fn read(&mut self, address: u8, buffer: &mut [u8]) -> Result<(), E> {
+        self.0.read(address, buffer).await
+    }
+
+    async fn write_read(&mut self, address: u8, bytes: &[u8], buffer: &mut [u8]) -> Result<(), E> {
+        self.0.write_read(address, bytes, buffer).await
+    }
+}
+
+/// SPI transactions used by the SPI expanders, on a device that handles its
+/// own chip select. Implemented for every blocking
+/// `embedded_hal::spi::SpiDevice`, and for `embedded_hal_async` devices
+/// wrapped in `AsyncSpi`.
+#[allow(async_fn_in_trait)]
+pub trait SpiBus {
+    /// Error of the underlying device
+    type Error;
+
+    /// Writes `bytes` in one chip select
+    async fn write(&mut self, bytes: &[u8]) -> Result<(), Self::Error>;
+
+    /// Sends `buffer` and replaces it with the bytes read back, in one chip
+    /// select
+    async fn transfer_in_place(&mut self, buffer: &mut [u8]) -> Result<(), Self::Error>;
+}
+
+impl<SPI, E> SpiBus for SPI
+where
+    SPI: embedded_hal::spi::SpiDevice<Error = E>,
+{
+    type Error = E;
+
+    async fn write(&mut self, bytes: &[u8]) -> Result<(), E> {
+        embedded_hal::spi::SpiDevice::write(self, bytes)
+    }
+
+    async fn transfer_in_place(&mut self, buffer: &mut [u8]) -> Result<(), E> {
+        embedded_hal::spi::SpiDevice::transfer_in_place(self, buffer)
+    }
+}
+
+/// Non-blocking SPI adapter, the counterpart of `AsyncI2c`
+pub struct AsyncSpi<SPI>(pub SPI);
+
+impl<SPI, E> SpiBus for AsyncSpi<SPI>
+where
+    SPI: embedded_hal_async::spi::SpiDevice<Error = E>,
+{
+    type Error = E;
+
+    async fn write(&mut self, bytes: &[u8]) -> Result<(), E> {
+        self.0.write(bytes).await
+    }
+
+    async fn transfer_in_place(&mut self, buffer: &mut [u8]) -> Result<(), E> {
+        self.0.transfer_in_place(buffer).await
+    }
+}
+
+/// Addresses used by PCF8574 (0x20-0x27), PCF8574A (0x38-0x3F) and TCA9534
+/// (0x20-0x27) backpacks, the usual 0x27 and 0x3F defaults first
+pub const COMMON_ADDRESSES: [u8; 16] = [
+    0x27, 0x3F, 0x20, 0x21, 0x22, 0x23, 0x24, 0x25, 0x26, 0x38, 0x39, 0x3A, 0x3B, 0x3C, 0x3D, 0x3E,
+];
+
+/// Returns the first address that acknowledges a one byte read. Reading is
+/// side-effect free on the supported expanders, unlike a port write.
+pub async fn detect<B: I2cBus>(i2c: &mut B, addresses: &[u8]) -> Option<u8> {
+    for &address in addresses {
+        if i2c.read(address, &mut [0]).await.is_ok() {
+            return Some(address);
+        }
+    }
+    None
+}
+
+// Bus transactions an expander has sent. Only kept with the `stats`
+// feature; without it this is zero-sized and `add` does nothing.
+#[derive(Clone, Copy, Default)]
+struct Sent(#[cfg(feature = "stats")] u32);
+
+impl Sent {
+    fn add(&mut self, transactions: u32) {
+        #[cfg(feature = "stats")]
+        {
+            self.0 = self.0.wrapping_add(transactions);
+        }
+        let _ = transactions;
+    }
+}
+
+/// An 8-bit port wired to the LCD as described by its [`PinMap`]. Only
+/// `write_port` and `read_port` are required; the other methods have
+/// defaults for plain GPIO expanders.
+#[allow(async_fn_in_trait)]
+pub trait PortExpander {
+    /// Error of the bus the expander sits on
+    type Error;
+
+    /// Wiring of the LCD to the port
+    fn pin_map(&self) -> PinMap {
+        PinMap::DEFAULT
+    }
+
+    /// Address the expander answers to on its bus, for diagnostics
+    fn bus_address(&self) -> Option<u8> {
+        None
+    }
+
+    /// Called from `LcdI2c::begin` and `LcdI2c::reset` before the LCD is
+    /// initialized. `initial` is the first port state to drive: every LCD
+    /// line low and the LED line at its configured level, which is high for
+    /// an active-low backlight that starts out off.
+    async fn init(&mut self, initial: u8) -> Result<(), Self::Error> {
+        self.write_port(initial).await
+    }
+
+    /// Drives the port to `value`
+    async fn write_port(&mut self, value: u8) -> Result<(), Self::Error>;
+
+    /// Writes several port states back to back. Expanders that latch every
+    /// byte of a multi-byte write override this to use a single transaction.
+    async fn write_port_sequence(&mut self, values: &[u8]) -> Result<(), Self::Error> {
+        for &value in values {
+            self.write_port(value).await?;
+        }
+        Ok(())
+    }
+
+    /// Reads the level of every pin on the port
+    async fn read_port(&mut self) -> Result<u8, Self::Error>;
+
+    /// Whether `read_port` returns what is on the pins. The driver treats an
+    /// expander that cannot read like `ReadCapability::WriteOnly`.
+    fn can_read(&self) -> bool {
+        true
+    }
+
+    /// Whether `set_backlight_pwm` can dim the backlight. Plain GPIO
+    /// expanders leave this false and the driver falls back to on/off.
+    fn has_backlight_pwm(&self) -> bool {
+        false
+    }
+
+    /// 0 is off, 255 full brightness
+    async fn set_backlight_pwm(&mut self, level: u8) -> Result<(), Self::Error> {
+        let _ = level;
+        Ok(())
+    }
+
+    /// Whether a second port is wired to D0-D7, which the 8-bit interface
+    /// needs. The PinMap then only describes the control port.
+    fn has_data_port(&self) -> bool {
+        false
+    }
+
+    /// Writes the control port and the D0-D7 port, in one transaction where
+    /// the expander allows it
+    async fn write_ports(&mut self, control: u8, data: u8) -> Result<(), Self::Error> {
+        let _ = data;
+        self.write_port(control).await
+    }
+
+    /// Reads D0-D7 from the data port; defaults to `read_port`
+    async fn read_data_port(&mut self) -> Result<u8, Self::Error> {
+        self.read_port().await
+    }
+
+    /// Switches the D4-D7 pins between inputs (while the LCD drives them
+    /// during a read) and outputs. Quasi-bidirectional ports such as the
+    /// PCF8574 only need the data bits written high, which the driver does.
+    async fn set_data_input(&mut self, input: bool) -> Result<(), Self::Error> {
+        let _ = input;
+        Ok(())
+    }
+
+    /// Whether `set_data_input` does anything. The driver skips the call
+    /// otherwise, so it neither shows up in the statistics nor counts as a
+    /// successful transaction after a string of bus errors.
+    fn has_data_direction(&self) -> bool {
+        false
+    }
+
+    /// Bus transactions sent since the expander was created, wrapping
+    #[cfg(feature = "stats")]
+    fn transactions(&self) -> u32 {
+        0
+    }
+}
+
+/// TI TCA9534 I2C GPIO expander, driven through its output and
+/// configuration registers
+pub struct Tca9534<I2C> {
+    i2c: I2C,
+    address: u8,
+    pins: PinMap,
+    config: u8,
+    polarity: u8,
+    sent: Sent,
+}
+
+impl<I2C> Tca9534<I2C> {
+    /// 7-bit `address`, 0x20 for all strapping pins low
+    pub fn new(i2c: I2C, address: u8) -> Self {
+        Self {
+            i2c,
+            address,
+            pins: PinMap::DEFAULT,
+            config: 0x00,
+            polarity: 0x00,
+            sent: Sent::default(),
+        }
+    }
+
+    /// Like `new`, but `None` unless `address` is one the TCA9534 (0x20-0x27)
+    /// or TCA9534A (0x38-0x3F) can be strapped to
+    pub fn new_checked(i2c: I2C, address: u8) -> Option<Self> {
+        matches!(address, 0x20..=0x27 | 0x38..=0x3F).then(|| Self::new(i2c, address))
+    }
+
+    /// Configuration register written by `init` (a set bit makes the pin an
+    /// input), for pins not used by the LCD. D4-D7 are switched to inputs
+    /// during reads and back to this value afterwards.
+    pub fn with_config(mut self, config: u8) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Polarity inversion register written by `init`
+    pub fn with_polarity(mut self, polarity: u8) -> Self {
+        self.polarity = polarity;
+        self
+    }
+
+    /// Wiring of the LCD to the port
+    pub fn with_pin_map(mut self, pins: PinMap) -> Self {
+        self.pins = pins;
+        self
+    }
+
+    /// Like [`Pcf8574::set_address`]
+    pub fn set_address(&mut self, address: u8) {
+        self.address = address;
+    }
+
+    /// Bus address transactions go to
+    pub fn address(&self) -> u8 {
+        self.address
+    }
+
+    /// Gives the bus back
+    pub fn release(self) -> I2C {
+        self.i2c
+    }
+}
+
+impl<I2C, E> Tca9534<I2C>
+where
+    I2C: I2cBus<Error = E>,
+{
+    async fn write_register(&mut self, register: u8, value: u8) -> Result<(), E> {
+        self.sent.add(1);
+        self.i2c.write(self.address, &[register, value]).await
+    }
+}
+
+impl<I2C, E> PortExpander for Tca9534<I2C>
+where
+    I2C: I2cBus<Error = E>,
+{
+    type Error = E;
+
+    fn pin_map(&self) -> PinMap {
+        self.pins
+    }
+
+    fn bus_address(&self) -> Option<u8> {
+        Some(self.address)
+    }
+
+    async fn init(&mut self, initial: u8) -> Result<(), E> {
+        // Configure the LCD pins as outputs (all of them by default)
+        self.write_register(TCA9534_REG_CONFIG, self.config).await?;
+
+        // Set polarity to normal unless configured otherwise
+        self.write_register(TCA9534_REG_POLARITY, self.polarity)
+            .await?;
+
+        self.write_port(initial).await
+    }
+
+    async fn write_port(&mut self, value: u8) -> Result<(), E> {
+        self.write_register(TCA9534_REG_OUTPUT, value).await
+    }
+
+    async fn read_port(&mut self) -> Result<u8, E> {
+        let mut buffer = [0];
+        self.sent.add(1);
+        self.i2c
+            .write_read(self.address, &[TCA9534_REG_INPUT], &mut buffer)
+            .await?;
+        Ok(buffer[0])
+    }
+
+    async fn set_data_input(&mut self, input: bool) -> Result<(), E> {
+        let config = if input {
+            self.config | 0x0F << self.pin_map().data
+        } else {
+            self.config
+        };
+        self.write_register(TCA9534_REG_CONFIG, config).await
+    }
+
+    fn has_data_direction(&self) -> bool {
+        true
+    }
+
+    #[cfg(feature = "stats")]
+    fn transactions(&self) -> u32 {
+        self.sent.0
+    }
+}
+
+/// Hands every port state to `write`, for expanders driven by other code
+/// (e.g. a port shared with unrelated GPIO). The closure cannot be read from,
+/// so reads fail with `Error::Unsupported` and busy polling stays off.
+pub struct FnExpander<F> {
+    write: F,
+    pins: PinMap,
+    last: u8,
+    sent: Sent,
+}
+
+impl<F> FnExpander<F> {
+    /// Wraps a closure that drives the port to the value it is given
+    pub fn new(write: F) -> Self {
+        Self {
+            write,
+            pins: PinMap::DEFAULT,
+            last: 0x00,
+            sent: Sent::default(),
+        }
+    }
+
+    /// Wiring of the LCD to the closure's port
+    pub fn with_pin_map(mut self, pins: PinMap) -> Self {
+        self.pins = pins;
+        self
+    }
+
+    /// Gives the closure back
+    pub fn release(self) -> F {
+        self.write
+    }
+}
+
+impl<F, E> PortExpander for FnExpander<F>
+where
+    F: FnMut(u8) -> Result<(), E>,
+{
+    type Error = E;
+
+    fn pin_map(&self) -> PinMap {
+        self.pins
+    }
+
+    async fn write_port(&mut self, value: u8) -> Result<(), E> {
+        self.sent.add(1);
+        (self.write)(value)?;
+        self.last = value;
+        Ok(())
+    }
+
+    // Never called by the driver, see `can_read`
+    async fn read_port(&mut self) -> Result<u8, E> {
+        Ok(self.last)
+    }
+
+    fn can_read(&self) -> bool {
+        false
+    }
+
+    #[cfg(feature = "stats")]
+    fn transactions(&self) -> u32 {
+        self.sent.0
+    }
+}
+
+/// The PCF8574 has no register pointer: every written byte is the port state
+pub struct Pcf8574<I2C> {
+    i2c: I2C,
+    address: u8,
+    pins: PinMap,
+    sent: Sent,
+}
+
+impl<I2C> Pcf8574<I2C> {
+    /// `address` is the 7-bit address, e.g. 0x27 rather than the shifted 0x4E
+    /// some datasheets and Arduino sketches give
+    pub fn new(i2c: I2C, address: u8) -> Self {
+        Self {
+            i2c,
+            address,
+            pins: PinMap::DEFAULT,
+            sent: Sent::default(),
+        }
+    }
+
+    /// Like `new`, but `None` unless `address` is one the PCF8574 (0x20-0x27)
+    /// or PCF8574A (0x38-0x3F) can be strapped to, which catches shifted
+    /// 8-bit addresses
+    pub fn new_checked(i2c: I2C, address: u8) -> Option<Self> {
+        matches!(address, 0x20..=0x27 | 0x38..=0x3F).then(|| Self::new(i2c, address))
+    }
+
+    /// Wiring of the LCD to the port, for backpacks that differ from
+    /// `PinMap::DEFAULT`
+    pub fn with_pin_map(mut self, pins: PinMap) -> Self {
+        self.pins = pins;
+        self
+    }
+
+    /// Retargets later transactions, e.g. at an address found by `detect`
+    /// after a different backpack was plugged in
+    pub fn set_address(&mut self, address: u8) {
+        self.address = address;
+    }
+
+    /// Bus address transactions go to
+    pub fn address(&self) -> u8 {
+        self.address
+    }
+
+    /// Gives the bus back
+    pub fn release(self) -> I2C {
+        self.i2c
+    }
+}
+
+impl<I2C, E> PortExpander for Pcf8574<I2C>
+where
+    I2C: I2cBus<Error = E>,
+{
+    type Error = E;
+
+    fn pin_map(&self) -> PinMap {
+        self.pins
+    }
+
+    fn bus_address(&self) -> Option<u8> {
+        Some(self.address)
+    }
+
+    async fn write_port(&mut self, value: u8) -> Result<(), E> {
+        self.sent.add(1);
+        self.i2c.write(self.address, &[value]).await
+    }
+
+    async fn write_port_sequence(&mut self, values: &[u8]) -> Result<(), E> {
+        self.sent.add(1);
+        self.i2c.write(self.address, values).await
+    }
+
+    async fn read_port(&mut self) -> Result<u8, E> {
+        let mut buffer = [0];
+        self.sent.add(1);
+        self.i2c.read(self.address, &mut buffer).await?;
+        Ok(buffer[0])
+    }
+
+    #[cfg(feature = "stats")]
+    fn transactions(&self) -> u32 {
+        self.sent.0
+    }
+}
+
+/// 16-bit quasi-bidirectional expander. Port 0 takes the control lines (and
+/// D4-D7 for the 4-bit interface); port 1 can carry D0-D7 for the 8-bit
+/// interface. Both ports are written in every transaction.
+pub struct Pcf8575<I2C> {
+    i2c: I2C,
+    address: u8,
+    pins: PinMap,
+    data: u8,
+    sent: Sent,
+}
+
+impl<I2C> Pcf8575<I2C> {
+    /// 7-bit `address`, as for the PCF8574
+    pub fn new(i2c: I2C, address: u8) -> Self {
+        Self {
+            i2c,
+            address,
+            pins: PinMap::DEFAULT,
+            data: 0x00,
+            sent: Sent::default(),
+        }
+    }
+
+    /// Like `new`, but `None` unless `address` is in the PCF8575's 0x20-0x27
+    pub fn new_checked(i2c: I2C, address: u8) -> Option<Self> {
+        (0x20..=0x27)
+            .contains(&address)
+            .then(|| Self::new(i2c, address))
+    }
+
+    /// Wiring of port 0
+    pub fn with_pin_map(mut self, pins: PinMap) -> Self {
+        self.pins = pins;
+        self
+    }
+
+    /// Like [`Pcf8574::set_address`]
+    pub fn set_address(&mut self, address: u8) {
+        self.address = address;
+    }
+
+    /// Bus address transactions go to
+    pub fn address(&self) -> u8 {
+        self.address
+    }
+
+    /// Gives the bus back
+    pub fn release(self) -> I2C {
+        self.i2c
+    }
+}
+
+impl<I2C, E> PortExpander for Pcf8575<I2C>
+where
+    I2C: I2cBus<Error = E>,
+{
+    type Error = E;
+
+    fn pin_map(&self) -> PinMap {
+        self.pins
+    }
+
+    fn bus_address(&self) -> Option<u8> {
+        Some(self.address)
+    }
+
+    async fn write_port(&mut self, value: u8) -> Result<(), E> {
+        self.sent.add(1);
+        self.i2c.write(self.address, &[value, self.data]).await
+    }
+
+    async fn read_port(&mut self) -> Result<u8, E> {
+        let mut buffer = [0; 2];
+        self.sent.add(1);
+        self.i2c.read(self.address, &mut buffer).await?;
+        Ok(buffer[0])
+    }
+
+    fn has_data_port(&self) -> bool {
+        true
+    }
+
+    async fn write_ports(&mut self, control: u8, data: u8) -> Result<(), E> {
+        self.data = data;
+        self.write_port(control).await
+    }
+
+    async fn read_data_port(&mut self) -> Result<u8, E> {
+        let mut buffer = [0; 2];
+        self.sent.add(1);
+        self.i2c.read(self.address, &mut buffer).await?;
+        Ok(buffer[1])
+    }
+
+    #[cfg(feature = "stats")]
+    fn transactions(&self) -> u32 {
+        self.sent.0
+    }
+}
+
+/// SPI counterpart of the MCP23008. `address` is the A1:A0 hardware address,
+/// 0-3; it is only decoded once `init` has enabled HAEN.
+pub struct Mcp23s08<SPI> {
+    spi: SPI,
+    address: u8,
+    pins: PinMap,
+    sent: Sent,
+}
+
+impl<SPI> Mcp23s08<SPI> {
+    /// Only the low two bits of `address` are used
+    pub fn new(spi: SPI, address: u8) -> Self {
+        Self {
+            spi,
+            address: address & 0x03,
+            pins: PinMap::DEFAULT,
+            sent: Sent::default(),
+        }
+    }
+
+    /// Wiring of the LCD to the port
+    pub fn with_pin_map(mut self, pins: PinMap) -> Self {
+        self.pins = pins;
+        self
+    }
+
+    /// Gives the SPI device back
+    pub fn release(self) -> SPI {
+        self.spi
+    }
+
+    fn opcode(&self, read: bool) -> u8 {
+        0x40 | self.address << 1 | read as u8
+    }
+}
+
+impl<SPI, E> Mcp23s08<SPI>
+where
+    SPI: SpiBus<Error = E>,
+{
+    async fn write_register(&mut self, register: u8, value: u8) -> Result<(), E> {
+        let opcode = self.opcode(false);
+        self.sent.add(1);
+        self.spi.write(&[opcode, register, value]).await
+    }
+}
+
+impl<SPI, E> PortExpander for Mcp23s08<SPI>
+where
+    SPI: SpiBus<Error = E>,
+{
+    type Error = E;
+
+    fn pin_map(&self) -> PinMap {
+        self.pins
+    }
+
+    fn bus_address(&self) -> Option<u8> {
+        Some(self.address)
+    }
+
+    async fn init(&mut self, initial: u8) -> Result<(), E> {
+        // Enable the hardware address and keep the register pointer on OLAT
+        // for sequential writes (HAEN | SEQOP)
+        self.write_register(MCP23S08_REG_IOCON, 0x28).await?;
+
+        // Configure all pins as outputs
+        self.write_register(MCP23S08_REG_IODIR, 0x00).await?;
+
+        // Set polarity to normal
+        self.write_register(MCP23S08_REG_IPOL, 0x00).await?;
+
+        self.write_port(initial).await
+    }
+
+    async fn write_port(&mut self, value: u8) -> Result<(), E> {
+        self.write_register(MCP23S08_REG_OLAT, value).await
+    }
+
+    // Each byte is latched as it is shifted in. At 10MHz that is 0.8us per
+    // byte, still above the 450ns E pulse minimum.
+    async fn write_port_sequence(&mut self, values: &[u8]) -> Result<(), E> {
+        let mut buffer = [0; 6];
+        for chunk in values.chunks(4) {
+            buffer[0] = self.opcode(false);
+            buffer[1] = MCP23S08_REG_OLAT;
+            buffer[2..2 + chunk.len()].copy_from_slice(chunk);
+            self.sent.add(1);
+            self.spi.write(&buffer[..2 + chunk.len()]).await?;
+        }
+        Ok(())
+    }
+
+    async fn read_port(&mut self) -> Result<u8, E> {
+        let mut buffer = [self.opcode(true), MCP23S08_REG_GPIO, 0];
+        self.sent.add(1);
+        self.spi.transfer_in_place(&mut buffer).await?;
+        Ok(buffer[2])
+    }
+
+    async fn set_data_input(&mut self, input: bool) -> Result<(), E> {
+        let direction = if input {
+            0x0F << self.pin_map().data
+        } else {
+            0x00
+        };
+        self.write_register(MCP23S08_REG_IODIR, direction).await
+    }
+
+    fn has_data_direction(&self) -> bool {
+        true
+    }
+
+    #[cfg(feature = "stats")]
+    fn transactions(&self) -> u32 {
+        self.sent.0
+    }
+}
@@ -0,0 +1,81 @@
+//! Driver with the geometry in the type, see [`LcdI2cFixed`]
+
+use core::ops::{Deref, DerefMut};
+
+use embedded_hal_async::delay::DelayNs;
+
+use crate::{Dimensions, Error, LcdConfig, LcdI2c, PortExpander};
+
+/// `LcdI2c` with the geometry in the type. Impossible geometries fail to
+/// compile, and `set_cursor_at` checks the position at compile time too.
+/// Everything else is the runtime driver, reached via `Deref`.
+///
+/// Displays with a second controller (`PinMap::e2`) hold up to 160 cells,
+/// so that is the compile-time limit. Whether the pin map actually has E2
+/// is only known at runtime: `new_with_config` rejects more than 80 cells
+/// per controller, `new` takes the geometry on trust like
+/// `LcdI2c::new_with_size`.
+pub struct LcdI2cFixed<EXP, D, const COLS: u8, const ROWS: u8> {
+    inner: LcdI2c<EXP, D>,
+}
+
+impl<EXP, D, E, const COLS: u8, const ROWS: u8> LcdI2cFixed<EXP, D, COLS, ROWS>
+where
+    EXP: PortExpander<Error = E>,
+    D: DelayNs,
+{
+    /// `COLS` x `ROWS`, checked at compile time
+    pub const DIMENSIONS: Dimensions = {
+        assert!(ROWS >= 1 && ROWS <= 4, "1 to 4 rows are supported");
+        assert!(
+            COLS as u16 * ROWS as u16 <= 160,
+            "geometry exceeds the DDRAM of two controllers"
+        );
+        Dimensions::new(COLS, ROWS)
+    };
+
+    /// Like [`LcdI2c::new_with_size`] with `COLS` x `ROWS`
+    pub fn new(expander: EXP, delay: D) -> Self {
+        Self {
+            inner: LcdI2c::new_with_size(expander, Self::DIMENSIONS, delay),
+        }
+    }
+
+    /// The dimensions in `config` are replaced by `COLS` x `ROWS`
+    pub fn new_with_config(expander: EXP, config: LcdConfig, delay: D) -> Result<Self, Error<E>> {
+        let config = config.dimensions(Self::DIMENSIONS);
+        Ok(Self {
+            inner: LcdI2c::new_with_config(expander, config, delay)?,
+        })
+    }
+
+    /// The runtime driver, dropping the geometry from the type
+    pub fn into_inner(self) -> LcdI2c<EXP, D> {
+        self.inner
+    }
+
+    /// [`LcdI2c::set_cursor`] with the position checked at compile time
+    pub async fn set_cursor_at<const COL: u8, const ROW: u8>(&mut self) -> Result<(), Error<E>> {
+        const {
+            assert!(
+                COL < COLS && ROW < ROWS,
+                "cursor position outside the display"
+            );
+        }
+        self.inner.set_cursor(COL, ROW).await
+    }
+}
+
+impl<EXP, D, const COLS: u8, const ROWS: u8> Deref for LcdI2cFixed<EXP, D, COLS, ROWS> {
+    type Target = LcdI2c<EXP, D>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+impl<EXP, D, const COLS: u8, const ROWS: u8> DerefMut for LcdI2cFixed<EXP, D, COLS, ROWS> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.inner
+    }
+}
@@ -0,0 +1,127 @@
+//! Off-screen display contents, see [`FrameBuffer`]
+
+use embedded_hal_async::delay::DelayNs;
+
+use crate::{Dimensions, Direction, Error, LcdI2c, PortExpander, RomVariant};
+
+// Each HD44780 has 80 bytes of DDRAM, and 40x4 displays pair two of them,
+// which bounds every supported geometry
+const DDRAM_SIZE: usize = 160;
+
+/// Off-screen copy of the display contents. Writes only touch the buffer;
+/// `flush` pushes the cells that differ from what was last sent.
+pub struct FrameBuffer {
+    dimensions: Dimensions,
+    desired: [u8; DDRAM_SIZE],
+    shown: [u8; DDRAM_SIZE],
+    synced: bool,
+    rom: RomVariant,
+}
+
+impl FrameBuffer {
+    /// Blank buffer for a display of `dimensions`. The first `flush` writes
+    /// every cell.
+    pub fn new(dimensions: Dimensions) -> Self {
+        // Geometries larger than the DDRAM are clipped to fit
+        let rows = dimensions.rows.min(4);
+        let cols = dimensions
+            .cols
+            .min((DDRAM_SIZE / rows.max(1) as usize) as u8);
+
+        Self {
+            dimensions: Dimensions::new(cols, rows),
+            desired: [b' '; DDRAM_SIZE],
+            shown: [b' '; DDRAM_SIZE],
+            synced: false,
+            rom: RomVariant::A00,
+        }
+    }
+
+    /// Character ROM `write_str_at` encodes for; use the display's
+    /// `LcdConfig::rom`
+    pub fn with_rom(mut self, rom: RomVariant) -> Self {
+        self.rom = rom;
+        self
+    }
+
+    /// Blanks the buffer; the display follows on the next `flush`
+    pub fn clear(&mut self) {
+        self.desired = [b' '; DDRAM_SIZE];
+    }
+
+    /// One cell per character, encoded like `LcdI2c::write_str`. Text past
+    /// the end of the row is cut off.
+    pub fn write_str_at(&mut self, col: u8, row: u8, s: &str) {
+        if col >= self.dimensions.cols || row >= self.dimensions.rows {
+            return;
+        }
+
+        let start = self.index(col, row);
+        let available = (self.dimensions.cols - col) as usize;
+        for (cell, c) in self.desired[start..]
+            .iter_mut()
+            .zip(s.chars().take(available))
+        {
+            *cell = self.rom.encode(c);
+        }
+    }
+
+    /// Forces the next `flush` to rewrite every cell, e.g. after the display
+    /// was cleared or reset behind the buffer's back
+    pub fn invalidate(&mut self) {
+        self.synced = false;
+    }
+
+    /// Sends each run of changed cells with a single `set_cursor`, honouring
+    /// the entry direction, and leaves the cursor at the end of the last run.
+    /// For [`LcdI2cBlocking`](crate::LcdI2cBlocking) use `flush_frame`.
+    pub async fn flush<EXP, D, E>(&mut self, lcd: &mut LcdI2c<EXP, D>) -> Result<(), Error<E>>
+    where
+        EXP: PortExpander<Error = E>,
+        D: DelayNs,
+    {
+        for row in 0..self.dimensions.rows {
+            let mut col = 0;
+            while col < self.dimensions.cols {
+                if self.is_current(col, row) {
+                    col += 1;
+                    continue;
+                }
+
+                // Push the whole run of changed cells after a single set_cursor
+                let start = col;
+                while col < self.dimensions.cols && !self.is_current(col, row) {
+                    col += 1;
+                }
+
+                // Right to left the run is written from its right end
+                let run = self.index(start, row)..self.index(col, row);
+                match lcd.text_direction() {
+                    Direction::LeftToRight => {
+                        lcd.set_cursor(start, row).await?;
+                        lcd.write_bytes(&self.desired[run.clone()]).await?;
+                    }
+                    Direction::RightToLeft => {
+                        lcd.set_cursor(col - 1, row).await?;
+                        for &byte in self.desired[run.clone()].iter().rev() {
+                            lcd.write_byte(byte).await?;
+                        }
+                    }
+                }
+                self.shown[run.clone()].copy_from_slice(&self.desired[run]);
+            }
+        }
+
+        self.synced = true;
+        Ok(())
+    }
+
+    fn is_current(&self, col: u8, row: u8) -> bool {
+        let index = self.index(col, row);
+        self.synced && self.desired[index] == self.shown[index]
+    }
+
+    fn index(&self, col: u8, row: u8) -> usize {
+        row as usize * self.dimensions.cols as usize + col as usize
+    }
+}
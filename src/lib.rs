@@ -1,19 +1,145 @@
+//! `no_std` driver for HD44780-compatible character LCDs (and WS0010
+//! character OLEDs) behind an I/O expander, such as the common PCF8574 I2C
+//! backpack.
+//!
+//! [`LcdI2c`] is the async driver, built on `embedded-hal-async`.
+//! [`LcdI2cBlocking`] wraps it for a blocking `embedded-hal` I2C bus and
+//! delay, with the same methods. Either drives the display through a
+//! [`PortExpander`]: [`Pcf8574`], [`Pcf8575`], [`Tca9534`], [`Mcp23s08`] over
+//! SPI, or [`FnExpander`] for a port driven by other code.
+//!
+//! ```no_run
+//! use embedded_hal::delay::DelayNs;
+//! use embedded_hal::i2c::I2c;
+//! use lcd_i2c::{Error, LcdI2cBlocking};
+//!
+//! fn hello<I2C: I2c, D: DelayNs>(i2c: I2C, delay: D) -> Result<(), Error<I2C::Error>> {
+//!     // PCF8574 at 0x27 driving a 16x2 display
+//!     let mut lcd = LcdI2cBlocking::default_16x2(i2c, delay);
+//!     lcd.begin()?;
+//!     lcd.backlight()?;
+//!     lcd.write_str_at(0, 0, "Hello")?;
+//!     Ok(())
+//! }
+//! ```
+//!
+//! Geometry, interface, timing and the character ROM are set with
+//! [`LcdConfig`] and [`LcdI2c::new_with_config`], which rejects combinations
+//! the hardware cannot do.
+//!
+//! # Sharing between tasks
+//!
+//! The driver is a plain value: every call takes `&mut self`, and the cursor
+//! position is part of its state. Tasks that update different regions hold
+//! it behind an async mutex and position the cursor inside every locked
+//! section, e.g. with [`LcdI2c::write_str_at`], instead of relying on where
+//! another task left it:
+//!
+//! ```ignore
+//! static LCD: Mutex<CriticalSectionRawMutex, Option<Lcd>> = Mutex::new(None);
+//!
+//! async fn show_temperature(celsius: i32) {
+//!     let mut guard = LCD.lock().await;
+//!     let lcd = guard.as_mut().unwrap();
+//!     lcd.set_cursor(0, 1).await.ok();
+//!     lcd.write_i32(celsius).await.ok();
+//! }
+//! ```
+//!
+//! # Features
+//!
+//! - `fmt` (default): `core::fmt::Write` for `write!` and `writeln!`
+//! - `buffered`: `FrameBuffer` and the row cache behind
+//!   `LcdI2c::update_line`
+//! - `stats`: `LcdI2c::transactions`, counting the bus transactions sent
+
 #![no_std]
+#![warn(missing_docs)]
 
+#[cfg(feature = "fmt")]
 use core::fmt;
 
-use embassy_time::Timer;
-use embedded_hal::i2c::I2c;
+use embedded_hal_async::delay::DelayNs;
+
+#[cfg(feature = "fmt")]
+use blocking::block_on;
+use charset::{BIG_DIGIT_GLYPHS, BIG_DIGITS};
+
+pub mod animation;
+pub mod blocking;
+pub mod charset;
+pub mod config;
+pub mod delay;
+pub mod expander;
+pub mod fixed;
+#[cfg(feature = "buffered")]
+pub mod framebuffer;
+
+pub use animation::Animation;
+pub use blocking::LcdI2cBlocking;
+pub use charset::{CgramSlot, RomVariant, charmap, charmap_bits};
+pub use config::{
+    Controller, Dimensions, Direction, Font, Interface, LcdConfig, Lines, ReadCapability,
+    TimingMode,
+};
+pub use delay::NoDelay;
+pub use expander::{
+    AsyncI2c, AsyncSpi, COMMON_ADDRESSES, FnExpander, I2cBus, Mcp23s08, Pcf8574, Pcf8575, PinMap,
+    PortExpander, SpiBus, Tca9534, detect,
+};
+pub use fixed::LcdI2cFixed;
+#[cfg(feature = "buffered")]
+pub use framebuffer::FrameBuffer;
+
+/// Errors returned by the driver. `E` is the expander's bus error.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Error<E> {
+    /// The expander reported a bus error
+    I2c(E),
+    /// The requested column/row lies outside the configured dimensions
+    InvalidCursor,
+    /// The configuration requests an unsupported combination of options
+    InvalidConfig,
+    /// The busy flag did not clear while polling
+    Timeout,
+    /// Too many consecutive bus errors; calls fail fast until `reconnect`
+    Disconnected,
+    /// Reading back the display after initialization gave unexpected data
+    InitFailed,
+    /// The operation reads from the display, but it is wired write-only
+    Unsupported,
+}
+
+// Renders the decimal digits of `value` into the end of `buffer` and returns
+// them, avoiding the `core::fmt` machinery
+fn format_u32(value: u32, buffer: &mut [u8; 10]) -> &[u8] {
+    let mut value = value;
+    let mut start = buffer.len();
+    loop {
+        start -= 1;
+        buffer[start] = b'0' + (value % 10) as u8;
+        value /= 10;
+        if value == 0 {
+            break;
+        }
+    }
+    &buffer[start..]
+}
 
-// TCA9534 registers
-const TCA9534_REG_OUTPUT: u8 = 0x01;
-const TCA9534_REG_POLARITY: u8 = 0x02;
-const TCA9534_REG_CONFIG: u8 = 0x03;
+// CGRAM layout of the built-in glyphs: `write_big_digit` bars in slots 0-2,
+// a full block in slot 3 for ROMs without one, and the four partial cells of
+// `progress_bar` in slots 4-7
+const FULL_BLOCK_SLOT: u8 = 3;
+const BAR_FIRST_SLOT: u8 = 4;
 
+/// Levels the driver keeps on the LCD lines between port writes. Only the
+/// driver itself reads or changes them.
 pub struct OutputState {
     rs: u8,
     rw: u8,
     e: u8,
+    // Which controllers E is routed to (bit 0 first, bit 1 second)
+    enables: u8,
     led: u8,
     data: u8,
 }
@@ -24,393 +150,1875 @@ impl OutputState {
             rs: 0,
             rw: 0,
             e: 0,
+            enables: 0b01,
             led: 0,
             data: 0,
         }
     }
 
-    fn get_low_data(&self) -> u8 {
-        let mut buffer = self.rs;
-        buffer |= self.rw << 1;
-        buffer |= self.e << 2;
-        buffer |= self.led << 3;
-        buffer |= (self.data & 0x0F) << 4;
-        buffer
+    fn get_low_data(&self, pins: &PinMap) -> u8 {
+        self.get_port_data(pins, self.data & 0x0F)
     }
 
-    fn get_high_data(&self) -> u8 {
-        let mut buffer = self.rs;
-        buffer |= self.rw << 1;
-        buffer |= self.e << 2;
-        buffer |= self.led << 3;
-        buffer |= self.data & 0xF0;
+    fn get_high_data(&self, pins: &PinMap) -> u8 {
+        self.get_port_data(pins, self.data >> 4)
+    }
+
+    fn get_port_data(&self, pins: &PinMap, nibble: u8) -> u8 {
+        let mut buffer = self.rs << pins.rs;
+        buffer |= self.rw << pins.rw;
+        buffer |= (self.e & self.enables) << pins.e;
+        if let Some(e2) = pins.e2 {
+            buffer |= (self.e & (self.enables >> 1)) << e2;
+        }
+        buffer |= self.led << pins.led;
+        buffer |= nibble << pins.data;
         buffer
     }
 }
 
-pub struct LcdI2c<I2C> {
-    i2c: I2C,
-    address: u8,
+/// Async HD44780 driver talking to the display through the expander `EXP`,
+/// waiting out instruction times with the delay `D`.
+///
+/// Construct it with [`new`](Self::new), [`new_with_size`](Self::new_with_size)
+/// or [`new_with_config`](Self::new_with_config), then call
+/// [`begin`](Self::begin) once the display has power. The driver tracks the
+/// cursor, display and entry mode state itself, so it only reads from the
+/// display for busy-flag polling and the explicit read methods.
+///
+/// Every operation takes `&mut self` and the cursor is shared state. Tasks
+/// sharing one display hold it behind an async mutex (for example
+/// `embassy_sync::mutex::Mutex`) and set the cursor inside each locked
+/// section; see the [crate docs](crate#sharing-between-tasks).
+pub struct LcdI2c<EXP, D> {
+    expander: EXP,
+    delay: D,
+    pins: PinMap,
+    config: LcdConfig,
+    row_offsets: [u8; 4],
     output: OutputState,
     display_state: u8,
     entry_state: u8,
+    busy_polling: bool,
+    // Software model of the DDRAM address counter
+    address: u8,
+    // Controller holding the cursor on dual-controller displays
+    controller: u8,
+    // Data writes go to CGRAM, which both controllers need a copy of
+    cgram: bool,
+    // Columns the display has been shifted left, modulo the line length
+    display_shift: u8,
+    failures: u8,
+    disconnected: bool,
+    // Set when an unchecked constructor got a configuration the hardware
+    // cannot do; every bus access then fails with `InvalidConfig`
+    misconfigured: bool,
+    busy_timeouts: u32,
+    // The `progress_bar` glyphs are in CGRAM
+    bar_glyphs: bool,
+    big_digit_glyphs: bool,
+    full_block_glyph: bool,
+    // Last level sent through the PWM hook, for `resume`
+    backlight_pwm: u8,
+    // Display control and backlight from before `suspend`
+    suspended: Option<(u8, bool)>,
+    // What each row was last written with, for `update_line`. A row is only
+    // trusted once its bit in `lines_known` is set.
+    #[cfg(feature = "buffered")]
+    lines: [[u8; 40]; 4],
+    #[cfg(feature = "buffered")]
+    lines_known: u8,
+    // Expander transaction count at the last `reset_transactions`
+    #[cfg(feature = "stats")]
+    transactions_reset: u32,
 }
 
-impl<I2C, E> LcdI2c<I2C>
+impl<EXP, D, E> LcdI2c<EXP, D>
 where
-    I2C: I2c<Error = E>,
+    EXP: PortExpander<Error = E>,
+    D: DelayNs,
 {
-    pub fn new(i2c: I2C, address: u8) -> Self {
+    /// 16x2 display with the default [`LcdConfig`]. Unlike
+    /// [`new_with_config`](Self::new_with_config) this cannot fail: when the
+    /// checks `new_with_config` does would fail (an invalid pin map, or with
+    /// `new_with_size` a geometry that does not fit the DDRAM), every later
+    /// call starting with `begin` returns `Error::InvalidConfig` without
+    /// touching the bus.
+    pub fn new(expander: EXP, delay: D) -> Self {
+        Self::new_with_size(expander, Dimensions::default(), delay)
+    }
+
+    /// Like [`new`](Self::new) for another geometry
+    pub fn new_with_size(expander: EXP, dimensions: Dimensions, delay: D) -> Self {
+        Self::from_config(expander, LcdConfig::new().dimensions(dimensions), delay)
+    }
+
+    /// Checks `config` against the expander up front: fails with
+    /// `Error::InvalidConfig` for geometries, pin maps or interfaces it cannot
+    /// drive. Nothing is sent until `begin`.
+    pub fn new_with_config(expander: EXP, config: LcdConfig, delay: D) -> Result<Self, Error<E>> {
+        if !Self::supports(&expander, &config) {
+            return Err(Error::InvalidConfig);
+        }
+        Ok(Self::from_config(expander, config, delay))
+    }
+
+    // Whether `config` can be driven through `expander`
+    fn supports(expander: &EXP, config: &LcdConfig) -> bool {
+        let eight_bit = config.interface == Interface::EightBit;
+        if !config.is_valid()
+            || !expander.pin_map().is_valid()
+            || (eight_bit && !expander.has_data_port())
+        {
+            return false;
+        }
+        // Each controller has 80 bytes of DDRAM for its (up to) two rows
+        let dimensions = config.dimensions;
+        let per_controller = match expander.pin_map().e2 {
+            Some(_) => dimensions.cols as u16 * dimensions.rows.min(2) as u16,
+            None => dimensions.cells(),
+        };
+        if per_controller > 80 {
+            return false;
+        }
+        // Row offsets are sent as the 7-bit set DDRAM address instruction
+        match config.row_offsets {
+            Some(offsets) => offsets.iter().all(|&offset| offset <= 0x7F),
+            None => true,
+        }
+    }
+
+    fn from_config(expander: EXP, config: LcdConfig, delay: D) -> Self {
+        // Nothing is sent for an unsupported configuration. The default
+        // pins keep the port arithmetic in range for an invalid map.
+        let misconfigured = !Self::supports(&expander, &config);
+        let pins = if expander.pin_map().is_valid() {
+            expander.pin_map()
+        } else {
+            PinMap::DEFAULT
+        };
+        // Each controller of a dual-controller display addresses its two
+        // rows at 0x00 and 0x40
+        let row_offsets = match (config.row_offsets, pins.e2) {
+            (Some(offsets), _) => offsets,
+            (None, Some(_)) => [0x00, 0x40, 0x00, 0x40],
+            (None, None) => config.dimensions.row_offsets(),
+        };
+
         Self {
-            i2c,
-            address,
+            pins,
+            expander,
+            delay,
+            config,
+            row_offsets,
             output: OutputState::new(),
             display_state: 0x00,
-            entry_state: 0x00,
+            // Left to right, the entry mode `begin` programs
+            entry_state: 0x02,
+            busy_polling: false,
+            address: 0x00,
+            controller: 0,
+            cgram: false,
+            display_shift: 0,
+            failures: 0,
+            disconnected: false,
+            misconfigured,
+            busy_timeouts: 0,
+            bar_glyphs: false,
+            big_digit_glyphs: false,
+            full_block_glyph: false,
+            backlight_pwm: u8::MAX,
+            suspended: None,
+            #[cfg(feature = "buffered")]
+            lines: [[b' '; 40]; 4],
+            #[cfg(feature = "buffered")]
+            lines_known: 0,
+            #[cfg(feature = "stats")]
+            transactions_reset: 0,
         }
     }
 
-    pub async fn begin(&mut self) -> Result<(), E> {
-        // Initialize TCA9534 I/O expander
-        // Configure all pins as outputs
-        self.i2c.write(self.address, &[TCA9534_REG_CONFIG, 0x00])?;
-        Timer::after_millis(10).await;
+    /// Hands back the expander and delay, e.g. to reuse a shared bus
+    pub fn release(self) -> (EXP, D) {
+        (self.expander, self.delay)
+    }
 
-        // Set polarity to normal
-        self.i2c
-            .write(self.address, &[TCA9534_REG_POLARITY, 0x00])?;
-        Timer::after_millis(10).await;
+    /// The expander the driver talks through
+    pub fn expander(&self) -> &EXP {
+        &self.expander
+    }
+
+    /// For expander settings such as the bus address. Call `reset` afterwards
+    /// if the expander now talks to a different display.
+    pub fn expander_mut(&mut self) -> &mut EXP {
+        &mut self.expander
+    }
 
-        // Set all outputs low initially
-        self.i2c.write(self.address, &[TCA9534_REG_OUTPUT, 0x00])?;
-        Timer::after_millis(10).await;
+    /// Initializes the expander and the LCD, then applies the display
+    /// control, entry mode and backlight from the configuration. Waits
+    /// `power_on_delay_ms` first, for a display powered up at the same time.
+    pub async fn begin(&mut self) -> Result<(), Error<E>> {
+        // OLEDs have no backlight; keep the LED pin low from `init` on
+        self.output.led = if self.config.controller == Controller::Ws0010 {
+            0
+        } else {
+            self.led_level(self.config.backlight)
+        };
+
+        // Wait for the supply to settle before the first command
+        self.delay.delay_ms(self.config.power_on_delay_ms).await;
+
+        // Initialize the I/O expander with the LCD lines low
+        self.ensure_connected()?;
+        let result = self.expander.init(self.output.led << self.pins.led).await;
+        self.track(result)?;
+        self.delay.delay_ms(10).await;
 
         // Initialize LCD
         self.initialize_lcd().await?;
 
+        if self.config.verify_init && self.can_read() {
+            let mut ready = false;
+            for _ in 0..self.config.busy_poll_limit.max(1) {
+                if self.is_ready().await? {
+                    ready = true;
+                    break;
+                }
+            }
+            if !ready {
+                return Err(Error::InitFailed);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Runs `begin` up to `attempts` times (at least once) for modules that
+    /// occasionally miss the reset sequence. With busy polling enabled each
+    /// attempt is checked by reading back the address counter, which the
+    /// final clear leaves at 0.
+    pub async fn begin_with_retries(&mut self, attempts: u8) -> Result<(), Error<E>> {
+        let mut result = Ok(());
+        for _ in 0..attempts.max(1) {
+            result = self.begin().await;
+            if result.is_ok() && self.busy_polling {
+                result = match self.read_address().await {
+                    Ok(0x00) => Ok(()),
+                    Ok(_) => Err(Error::InitFailed),
+                    Err(e) => Err(e),
+                };
+            }
+            if result.is_ok() {
+                break;
+            }
+        }
+        result
+    }
+
+    /// Re-runs the whole initialization (expander included) and then restores
+    /// the display control, entry mode and backlight state from before
+    pub async fn reset(&mut self) -> Result<(), Error<E>> {
+        let display_state = self.display_state;
+        let entry_state = self.entry_state;
+
+        self.delay.delay_ms(self.config.power_on_delay_ms).await;
+        self.ensure_connected()?;
+        let result = self.expander.init(self.output.led << self.pins.led).await;
+        self.track(result)?;
+        self.delay.delay_ms(10).await;
+        self.initialize_lcd().await?;
+
+        self.display_state = display_state;
+        self.write_display_control().await?;
+
+        self.entry_state = entry_state;
+        self.write_entry_mode().await?;
+
+        self.i2c_write(self.output.get_high_data(&self.pins))
+            .await?;
+        Ok(())
+    }
+
+    /// Lighter recovery than `reset` for when the nibbles have got out of step
+    /// (the display shows shifted garbage): repeats the mode sync and function
+    /// set, then restores display control, entry mode and the cursor. DDRAM is
+    /// not cleared, although the sync can complete one garbled instruction.
+    pub async fn resync_4bit(&mut self) -> Result<(), Error<E>> {
+        self.output.rs = 0;
+        self.output.rw = 0;
+
+        match (self.config.controller, self.config.interface) {
+            (Controller::Ws0010, Interface::FourBit) => self.sync_ws0010().await?,
+            _ => self.sync_hd44780().await?,
+        }
+
+        self.lcd_write(self.config.function_set(), false).await?;
+        self.instruction_delay(37).await;
+
+        self.write_display_control().await?;
+        self.write_entry_mode().await?;
+        self.set_ddram_address(self.address).await
+    }
+
+    /// False once `disconnect_after` bus errors in a row have been seen;
+    /// `reconnect` brings the display back
+    pub fn is_connected(&self) -> bool {
+        !self.disconnected
+    }
+
+    /// Leaves the disconnected state and reinitializes the display. Fails
+    /// (and counts towards disconnecting again) if it still does not respond.
+    pub async fn reconnect(&mut self) -> Result<(), Error<E>> {
+        self.disconnected = false;
+        self.failures = 0;
+        self.reset().await
+    }
+
+    /// Rewrites the current port state with E low, which the display ignores,
+    /// to check that the expander still ACKs. Works while disconnected too, so
+    /// a heartbeat can tell when `reconnect` is worth trying.
+    pub async fn ping(&mut self) -> Result<(), Error<E>> {
+        if self.misconfigured {
+            return Err(Error::InvalidConfig);
+        }
+        let e = core::mem::replace(&mut self.output.e, 0);
+        let port = self.output.get_high_data(&self.pins);
+        self.output.e = e;
+        let result = match self.config.interface {
+            Interface::FourBit => self.expander.write_port(port).await,
+            Interface::EightBit => self.expander.write_ports(port, self.output.data).await,
+        };
+        self.track(result)
+    }
+
+    async fn write_display_control(&mut self) -> Result<(), Error<E>> {
+        self.output.rs = 0;
+        self.output.rw = 0;
+
+        self.lcd_write(0x08 | self.display_state, false).await?;
+        self.instruction_delay(37).await;
+
         Ok(())
     }
 
-    async fn initialize_lcd(&mut self) -> Result<(), E> {
+    async fn write_entry_mode(&mut self) -> Result<(), Error<E>> {
+        self.output.rs = 0;
+        self.output.rw = 0;
+
+        self.lcd_write(0x04 | self.entry_state, false).await?;
+        self.instruction_delay(37).await;
+
+        Ok(())
+    }
+
+    async fn initialize_lcd(&mut self) -> Result<(), Error<E>> {
         // See HD44780U datasheet "Initializing by Instruction" Figure 24 (4-Bit Interface)
         self.output.rs = 0;
         self.output.rw = 0;
 
-        // Wait for more than 15ms after VCC rises to 4.5V
-        Timer::after_millis(50).await;
+        // The power-on wait has already been done by `begin`/`reset`
+
+        // CGRAM does not survive a power cycle, and after a swap it belongs to
+        // a different module, so the built-in glyphs are reloaded on next use
+        self.bar_glyphs = false;
+        self.big_digit_glyphs = false;
+        self.full_block_glyph = false;
+
+        match (self.config.controller, self.config.interface) {
+            (Controller::Ws0010, Interface::FourBit) => self.sync_ws0010().await?,
+            _ => self.sync_hd44780().await?,
+        }
+
+        // Function set: interface width, configured line count and font
+        self.lcd_write(self.config.function_set(), false).await?;
+        self.instruction_delay(37).await;
+
+        // WS0010: character mode with the internal power supply on
+        if self.config.controller == Controller::Ws0010 {
+            self.lcd_write(0x17, false).await?;
+            self.instruction_delay(37).await;
+        }
+
+        // Keep the display off until DDRAM has been cleared, otherwise
+        // whatever it held at power-up flashes on screen
+        self.display_state = (self.config.cursor as u8) << 1 | self.config.blink as u8;
+        self.write_display_control().await?;
+
+        self.clear().await?;
+
+        self.left_to_right().await?;
+
+        self.display().await?;
+
+        Ok(())
+    }
+
+    async fn sync_hd44780(&mut self) -> Result<(), Error<E>> {
+        // The three 0x3 nibbles bring the controller to 8-bit mode from any
+        // state it was left in by a warm reset of the MCU:
+        // - 8-bit mode: each nibble is a function set to 8-bit
+        // - 4-bit mode: the first two form 0x33, the third is then 8-bit
+        // - 4-bit mode, half a byte received: the first nibble completes an
+        //   arbitrary instruction (at worst a 1.52ms clear, covered by the
+        //   4.2ms wait) and the next two form 0x33
 
         // First attempt - 8-bit mode
         self.lcd_write(0x30, true).await?;
-        Timer::after_micros(4200).await;
+        self.delay.delay_us(4200).await;
 
         // Second attempt - 8-bit mode
         self.lcd_write(0x30, true).await?;
-        Timer::after_micros(150).await;
+        self.delay.delay_us(150).await;
 
         // Third attempt - 8-bit mode
         self.lcd_write(0x30, true).await?;
-        Timer::after_micros(37).await;
+        self.delay.delay_us(37).await;
 
         // Set to 4-bit mode
-        self.lcd_write(0x20, true).await?;
-        Timer::after_micros(37).await;
-
-        // Function set: 4-bit mode, 2 lines, 5x8 font
-        self.lcd_write(0x28, false).await?;
-        Timer::after_micros(37).await;
+        if self.config.interface == Interface::FourBit {
+            self.lcd_write(0x20, true).await?;
+            self.delay.delay_us(37).await;
+        }
 
-        self.display().await?;
+        Ok(())
+    }
 
-        self.clear().await?;
+    // The WS0010 does not need the triple 0x3, and a single one is enough to
+    // bring it back to 8-bit mode after a warm restart. Its timings are not
+    // specified, these are known to work on cold and warm starts.
+    async fn sync_ws0010(&mut self) -> Result<(), Error<E>> {
+        self.lcd_write(0x30, true).await?;
+        self.delay.delay_ms(5).await;
 
-        self.left_to_right().await?;
+        self.lcd_write(0x20, true).await?;
+        self.delay.delay_ms(5).await;
 
         Ok(())
     }
 
-    pub async fn clear(&mut self) -> Result<(), E> {
+    /// Both clear and home take up to 1.52ms (datasheet, 270kHz oscillator;
+    /// 6.2ms on the WS0010).
+    /// When every cell is rewritten anyway, `home` followed by the new
+    /// contents avoids the blank frame between updates; if the display is
+    /// never shifted, `set_cursor(0, 0)` does the same in 37us.
+    pub async fn clear(&mut self) -> Result<(), Error<E>> {
         self.output.rs = 0;
         self.output.rw = 0;
 
         self.lcd_write(0x01, false).await?;
-        Timer::after_micros(1600).await;
+        self.instruction_delay(self.config.controller.clear_us())
+            .await;
+        self.address = 0x00;
+        self.controller = 0;
+        self.display_shift = 0;
+        #[cfg(feature = "buffered")]
+        {
+            self.lines = [[b' '; 40]; 4];
+            self.lines_known = 0x0F;
+        }
 
         Ok(())
     }
 
-    pub async fn home(&mut self) -> Result<(), E> {
+    /// Moves the cursor to (0, 0) and undoes any display shift, leaving DDRAM
+    /// as it is
+    pub async fn home(&mut self) -> Result<(), Error<E>> {
         self.output.rs = 0;
         self.output.rw = 0;
 
         self.lcd_write(0x02, false).await?;
-        Timer::after_micros(1600).await;
+        self.instruction_delay(self.config.controller.clear_us())
+            .await;
+        self.address = 0x00;
+        self.controller = 0;
+        self.display_shift = 0;
 
         Ok(())
     }
 
-    pub async fn display(&mut self) -> Result<(), E> {
+    /// Shows the DDRAM contents again, unchanged
+    pub async fn display(&mut self) -> Result<(), Error<E>> {
         self.output.rs = 0;
         self.output.rw = 0;
 
         self.display_state |= 1 << 2;
 
         self.lcd_write(0x08 | self.display_state, false).await?;
-        Timer::after_micros(37).await;
+        self.instruction_delay(37).await;
 
         Ok(())
     }
 
-    pub async fn no_display(&mut self) -> Result<(), E> {
+    /// Blanks the display without touching DDRAM
+    pub async fn no_display(&mut self) -> Result<(), Error<E>> {
         self.output.rs = 0;
         self.output.rw = 0;
 
         self.display_state &= !(1 << 2);
 
         self.lcd_write(0x08 | self.display_state, false).await?;
-        Timer::after_micros(37).await;
+        self.instruction_delay(37).await;
 
         Ok(())
     }
 
-    pub async fn cursor(&mut self) -> Result<(), E> {
+    /// Shows the underline cursor
+    pub async fn cursor(&mut self) -> Result<(), Error<E>> {
         self.output.rs = 0;
         self.output.rw = 0;
 
         self.display_state |= 1 << 1;
 
         self.lcd_write(0x08 | self.display_state, false).await?;
-        Timer::after_micros(37).await;
+        self.instruction_delay(37).await;
 
         Ok(())
     }
 
-    pub async fn no_cursor(&mut self) -> Result<(), E> {
+    /// Hides the underline cursor
+    pub async fn no_cursor(&mut self) -> Result<(), Error<E>> {
         self.output.rs = 0;
         self.output.rw = 0;
 
         self.display_state &= !(1 << 1);
 
         self.lcd_write(0x08 | self.display_state, false).await?;
-        Timer::after_micros(37).await;
+        self.instruction_delay(37).await;
 
         Ok(())
     }
 
-    pub async fn blink(&mut self) -> Result<(), E> {
+    /// The blink rate is set by the controller's oscillator (409.6ms per
+    /// phase at 250kHz) and cannot be changed; see `blink_tick` for a
+    /// software blink at any rate
+    pub async fn blink(&mut self) -> Result<(), Error<E>> {
         self.output.rs = 0;
         self.output.rw = 0;
 
         self.display_state |= 1;
 
         self.lcd_write(0x08 | self.display_state, false).await?;
-        Timer::after_micros(37).await;
+        self.instruction_delay(37).await;
 
         Ok(())
     }
 
-    pub async fn no_blink(&mut self) -> Result<(), E> {
+    /// Stops the blinking block cursor
+    pub async fn no_blink(&mut self) -> Result<(), Error<E>> {
         self.output.rs = 0;
         self.output.rw = 0;
 
         self.display_state &= !1;
 
         self.lcd_write(0x08 | self.display_state, false).await?;
-        Timer::after_micros(37).await;
+        self.instruction_delay(37).await;
 
         Ok(())
     }
 
-    pub async fn left_to_right(&mut self) -> Result<(), E> {
-        self.output.rs = 0;
-        self.output.rw = 0;
+    /// Text runs left to right from the cursor (the default)
+    pub async fn left_to_right(&mut self) -> Result<(), Error<E>> {
+        self.set_text_direction(Direction::LeftToRight).await
+    }
 
-        self.entry_state |= 1 << 1;
+    /// Text runs right to left from the cursor
+    pub async fn right_to_left(&mut self) -> Result<(), Error<E>> {
+        self.set_text_direction(Direction::RightToLeft).await
+    }
 
-        self.lcd_write(0x04 | self.entry_state, false).await?;
-        Timer::after_micros(37).await;
+    /// Shifts the display with every character, so the cursor stays put and
+    /// the text scrolls past it
+    pub async fn autoscroll(&mut self) -> Result<(), Error<E>> {
+        self.entry_state |= 1;
+        self.write_entry_mode().await
+    }
 
-        Ok(())
+    /// Stops shifting the display on writes (the default)
+    pub async fn no_autoscroll(&mut self) -> Result<(), Error<E>> {
+        self.entry_state &= !1;
+        self.write_entry_mode().await
     }
 
-    pub async fn right_to_left(&mut self) -> Result<(), E> {
+    /// Shifts the whole display one column to the left without changing DDRAM
+    pub async fn scroll_display_left(&mut self) -> Result<(), Error<E>> {
         self.output.rs = 0;
         self.output.rw = 0;
 
-        self.entry_state &= !(1 << 1);
-
-        self.lcd_write(0x04 | self.entry_state, false).await?;
-        Timer::after_micros(37).await;
+        self.lcd_write(0x18, false).await?;
+        self.instruction_delay(37).await;
+        self.shift_display(true);
 
         Ok(())
     }
 
-    pub async fn autoscroll(&mut self) -> Result<(), E> {
+    /// Shifts the whole display one column to the right without changing DDRAM
+    pub async fn scroll_display_right(&mut self) -> Result<(), Error<E>> {
         self.output.rs = 0;
         self.output.rw = 0;
 
-        self.entry_state |= 1;
-
-        self.lcd_write(0x04 | self.entry_state, false).await?;
-        Timer::after_micros(37).await;
+        self.lcd_write(0x1C, false).await?;
+        self.instruction_delay(37).await;
+        self.shift_display(false);
 
         Ok(())
     }
 
-    pub async fn no_autoscroll(&mut self) -> Result<(), E> {
-        self.output.rs = 0;
-        self.output.rw = 0;
-
-        self.entry_state &= !1;
+    /// Columns the content is currently shifted left by scrolling or
+    /// autoscroll, from 0 up to the DDRAM line length (40, or 80 in one-line
+    /// mode) minus one
+    pub fn scroll_offset(&self) -> u8 {
+        self.display_shift
+    }
 
-        self.lcd_write(0x04 | self.entry_state, false).await?;
-        Timer::after_micros(37).await;
+    /// Shifts the display back to where `clear`/`home` left it, taking the
+    /// shorter direction. Unlike `home`, the cursor stays where it is.
+    pub async fn reset_scroll(&mut self) -> Result<(), Error<E>> {
+        let half = self.line_length() / 2;
+        while self.display_shift != 0 {
+            if self.display_shift <= half {
+                self.scroll_display_right().await?;
+            } else {
+                self.scroll_display_left().await?;
+            }
+        }
+        Ok(())
+    }
 
+    /// Shifts the display right by `amount` columns, or left when negative
+    pub async fn scroll_display(&mut self, amount: i8) -> Result<(), Error<E>> {
+        for _ in 0..amount.unsigned_abs() {
+            if amount > 0 {
+                self.scroll_display_right().await?;
+            } else {
+                self.scroll_display_left().await?;
+            }
+        }
         Ok(())
     }
 
-    pub async fn scroll_display_left(&mut self) -> Result<(), E> {
+    fn shift_display(&mut self, left: bool) {
+        let length = self.line_length();
+        self.display_shift = if left {
+            (self.display_shift + 1) % length
+        } else {
+            (self.display_shift + length - 1) % length
+        };
+    }
+
+    fn line_length(&self) -> u8 {
+        match self.config.lines {
+            Lines::One => 80,
+            Lines::Two => 40,
+        }
+    }
+
+    /// Moves the cursor one address back, without writing
+    pub async fn move_cursor_left(&mut self) -> Result<(), Error<E>> {
         self.output.rs = 0;
         self.output.rw = 0;
 
-        self.lcd_write(0x18, false).await?;
-        Timer::after_micros(37).await;
+        self.lcd_write(0x10, false).await?;
+        self.instruction_delay(37).await;
+        self.step_address(false);
 
         Ok(())
     }
 
-    pub async fn scroll_display_right(&mut self) -> Result<(), E> {
+    /// Moves the cursor one address forward, without writing
+    pub async fn move_cursor_right(&mut self) -> Result<(), Error<E>> {
         self.output.rs = 0;
         self.output.rw = 0;
 
-        self.lcd_write(0x1C, false).await?;
-        Timer::after_micros(37).await;
+        self.lcd_write(0x14, false).await?;
+        self.instruction_delay(37).await;
+        self.step_address(true);
 
         Ok(())
     }
 
-    pub fn backlight(&mut self) -> Result<(), E> {
-        self.output.led = 1;
-        self.i2c_write(0x00 | (self.output.led << 3))?;
+    /// Switches the backlight on, see `set_backlight`
+    pub async fn backlight(&mut self) -> Result<(), Error<E>> {
+        self.set_backlight(true).await
+    }
+
+    /// Switches the backlight off, see `set_backlight`
+    pub async fn no_backlight(&mut self) -> Result<(), Error<E>> {
+        self.set_backlight(false).await
+    }
+
+    /// Dims the backlight when the expander can, otherwise 0 switches it off
+    /// and anything else on
+    pub async fn set_backlight_level(&mut self, level: u8) -> Result<(), Error<E>> {
+        if self.expander.has_backlight_pwm() {
+            self.write_backlight_pwm(level).await?;
+            self.backlight_pwm = level;
+            Ok(())
+        } else {
+            self.set_backlight(level > 0).await
+        }
+    }
+
+    async fn write_backlight_pwm(&mut self, level: u8) -> Result<(), Error<E>> {
+        self.ensure_connected()?;
+        let result = self.expander.set_backlight_pwm(level).await;
+        self.track(result)
+    }
+
+    // Level of the LED line that turns the backlight on or off
+    fn led_level(&self, on: bool) -> u8 {
+        (on != self.config.backlight_active_low) as u8
+    }
+
+    /// Poll the busy flag over RW after every instruction instead of waiting
+    /// out the worst-case execution time. Requires RW and D4-D7 to be
+    /// readable through the expander; leave off for write-only wiring.
+    pub fn use_busy_flag(&mut self, enabled: bool) {
+        self.busy_polling = enabled && self.can_read();
+    }
+
+    /// `TimingMode::Busy` is `use_busy_flag(true)`
+    pub fn set_timing_mode(&mut self, mode: TimingMode) {
+        self.use_busy_flag(mode == TimingMode::Busy);
+    }
+
+    /// `Busy` only while busy-flag polling is actually in use
+    pub fn timing_mode(&self) -> TimingMode {
+        if self.busy_polling {
+            TimingMode::Busy
+        } else {
+            TimingMode::FixedDelay
+        }
+    }
+
+    /// Number of times busy-flag polling gave up with `Error::Timeout` since
+    /// the driver was created. A count that keeps growing points to a slow or
+    /// failing display.
+    pub fn busy_timeouts(&self) -> u32 {
+        self.busy_timeouts
+    }
+
+    /// Bus transactions the expander has sent (register writes, batched
+    /// sequences and reads, as they go out on I2C or SPI) since the driver
+    /// was created or the counter was last reset
+    #[cfg(feature = "stats")]
+    pub fn transactions(&self) -> u32 {
+        self.expander
+            .transactions()
+            .wrapping_sub(self.transactions_reset)
+    }
+
+    /// Starts `transactions` over from 0
+    #[cfg(feature = "stats")]
+    pub fn reset_transactions(&mut self) {
+        self.transactions_reset = self.expander.transactions();
+    }
+
+    /// `display` or `no_display`
+    pub async fn set_display(&mut self, on: bool) -> Result<(), Error<E>> {
+        if on {
+            self.display().await
+        } else {
+            self.no_display().await
+        }
+    }
+
+    /// `cursor` or `no_cursor`
+    pub async fn set_cursor_visible(&mut self, visible: bool) -> Result<(), Error<E>> {
+        if visible {
+            self.cursor().await
+        } else {
+            self.no_cursor().await
+        }
+    }
+
+    /// `blink` or `no_blink`
+    pub async fn set_blink(&mut self, on: bool) -> Result<(), Error<E>> {
+        if on {
+            self.blink().await
+        } else {
+            self.no_blink().await
+        }
+    }
+
+    /// Changes only the direction, leaving autoscroll as it is
+    pub async fn set_text_direction(&mut self, direction: Direction) -> Result<(), Error<E>> {
+        match direction {
+            Direction::LeftToRight => self.entry_state |= 1 << 1,
+            Direction::RightToLeft => self.entry_state &= !(1 << 1),
+        }
+        self.write_entry_mode().await
+    }
+
+    /// `autoscroll` or `no_autoscroll`
+    pub async fn set_autoscroll(&mut self, on: bool) -> Result<(), Error<E>> {
+        if on {
+            self.autoscroll().await
+        } else {
+            self.no_autoscroll().await
+        }
+    }
+
+    /// Sets display, cursor and blink with a single display control command
+    pub async fn set_display_control(
+        &mut self,
+        display: bool,
+        cursor: bool,
+        blink: bool,
+    ) -> Result<(), Error<E>> {
+        self.display_state = (display as u8) << 2 | (cursor as u8) << 1 | blink as u8;
+        self.write_display_control().await
+    }
+
+    /// Puts the blinking block at (col, row), e.g. on the field being edited.
+    /// Skips the display control write when blink is already on.
+    pub async fn blink_at(&mut self, col: u8, row: u8) -> Result<(), Error<E>> {
+        self.set_cursor(col, row).await?;
+        if !self.is_blink_on() {
+            self.blink().await?;
+        }
         Ok(())
     }
 
-    pub fn no_backlight(&mut self) -> Result<(), E> {
-        self.output.led = 0;
-        self.i2c_write(0x00 | (self.output.led << 3))?;
+    /// Toggles the underline cursor. Calling this from a timer blinks the
+    /// cursor at the timer's rate; it only changes the cursor bit, so leave
+    /// the hardware blink off. `stop_editing` hides it again.
+    pub async fn blink_tick(&mut self) -> Result<(), Error<E>> {
+        self.display_state ^= 1 << 1;
+        self.write_display_control().await
+    }
+
+    /// Hides both the underline cursor and the blinking block
+    pub async fn stop_editing(&mut self) -> Result<(), Error<E>> {
+        if self.display_state & 0b11 != 0 {
+            self.display_state &= !0b11;
+            self.write_display_control().await?;
+        }
         Ok(())
     }
 
-    pub async fn set_cursor(&mut self, col: u8, row: u8) -> Result<(), E> {
+    /// Sets text direction and autoscroll with a single entry mode command
+    pub async fn set_entry_mode(
+        &mut self,
+        direction: Direction,
+        autoscroll: bool,
+    ) -> Result<(), Error<E>> {
+        let increment = (direction == Direction::LeftToRight) as u8;
+        self.entry_state = increment << 1 | autoscroll as u8;
+        self.write_entry_mode().await
+    }
+
+    /// Rewrites the whole port in one write with only the LED line changed,
+    /// keeping RS/RW/E/data as they are. Expanders with a PWM backlight are
+    /// set to full or no brightness instead.
+    pub async fn set_backlight(&mut self, on: bool) -> Result<(), Error<E>> {
+        // OLEDs have no backlight, and the LED pin may be wired to something else
+        if self.config.controller == Controller::Ws0010 {
+            return Ok(());
+        }
+
+        self.output.led = self.led_level(on);
+        if self.expander.has_backlight_pwm() {
+            // The LED line may not reach the backlight, so switch it through
+            // the PWM channel as full or no brightness
+            let level = if on { u8::MAX } else { 0 };
+            self.write_backlight_pwm(level).await?;
+            self.backlight_pwm = level;
+            return Ok(());
+        }
+        self.i2c_write(self.output.get_high_data(&self.pins)).await
+    }
+
+    /// Turns the display and backlight off for idle periods. DDRAM survives,
+    /// and `resume` brings back the display control and backlight state,
+    /// including the level of a dimmed PWM backlight.
+    pub async fn suspend(&mut self) -> Result<(), Error<E>> {
+        if self.suspended.is_none() {
+            let backlight = self.output.led == self.led_level(true);
+            self.suspended = Some((self.display_state, backlight));
+        }
+        self.no_display().await?;
+        if self.expander.has_backlight_pwm() {
+            // Leaves `backlight_pwm` alone so `resume` can bring it back
+            self.write_backlight_pwm(0).await
+        } else {
+            self.set_backlight(false).await
+        }
+    }
+
+    /// Undoes `suspend`; does nothing if the display was not suspended
+    pub async fn resume(&mut self) -> Result<(), Error<E>> {
+        let Some((display_state, backlight)) = self.suspended.take() else {
+            return Ok(());
+        };
+        self.display_state = display_state;
+        self.write_display_control().await?;
+        if self.expander.has_backlight_pwm() {
+            self.set_backlight_level(self.backlight_pwm).await
+        } else {
+            self.set_backlight(backlight).await
+        }
+    }
+
+    /// Visible size of the display
+    pub fn dimensions(&self) -> Dimensions {
+        self.config.dimensions
+    }
+
+    /// Current (col, row), derived from the tracked address counter. Text that
+    /// runs off the end of a row shows up wherever the DDRAM layout takes it,
+    /// e.g. row 0 continues on row 2 of a 20x4.
+    pub fn position(&self) -> (u8, u8) {
+        let rows = self.config.dimensions.rows.clamp(1, 4);
+
+        let mut position = (self.address, self.controller * 2);
+        for row in 0..rows {
+            if self.row_controller(row) != self.controller {
+                continue;
+            }
+            let offset = self.row_offsets[row as usize];
+            if self.address >= offset && self.address - offset < position.0 {
+                position = (self.address - offset, row);
+            }
+        }
+        position
+    }
+
+    /// Whether the display is on, as last set through the driver
+    pub fn is_display_on(&self) -> bool {
+        self.display_state & (1 << 2) != 0
+    }
+
+    /// Whether the underline cursor is shown
+    pub fn is_cursor_on(&self) -> bool {
+        self.display_state & (1 << 1) != 0
+    }
+
+    /// Whether the block cursor blinks
+    pub fn is_blink_on(&self) -> bool {
+        self.display_state & 1 != 0
+    }
+
+    /// Whether writes shift the display
+    pub fn is_autoscroll(&self) -> bool {
+        self.entry_state & 1 != 0
+    }
+
+    /// Direction the entry mode moves the cursor in
+    pub fn text_direction(&self) -> Direction {
+        if self.entry_state & (1 << 1) != 0 {
+            Direction::LeftToRight
+        } else {
+            Direction::RightToLeft
+        }
+    }
+
+    /// Positions outside the configured dimensions are rejected with
+    /// `InvalidCursor` and nothing is sent, rather than wrapping or clamping.
+    /// (col, row) names the same cell in either entry direction; right to left
+    /// only changes which way the cursor moves after each write.
+    pub async fn set_cursor(&mut self, col: u8, row: u8) -> Result<(), Error<E>> {
         self.output.rs = 0;
         self.output.rw = 0;
 
-        let new_address = if row == 0 { 0x00 } else { 0x40 } + col;
+        let dimensions = self.config.dimensions;
+        if col >= dimensions.cols || row >= dimensions.rows.min(4) {
+            return Err(Error::InvalidCursor);
+        }
+
+        // Custom row offsets can put the end of a row past the 7-bit address
+        let new_address = match self.row_offsets[row as usize].checked_add(col) {
+            Some(address) if address <= 0x7F => address,
+            _ => return Err(Error::InvalidCursor),
+        };
+
+        self.controller = self.row_controller(row);
+        self.set_ddram_address(new_address).await
+    }
 
-        self.lcd_write(0x80 | new_address, false).await?;
-        Timer::after_micros(37).await;
+    fn row_controller(&self, row: u8) -> u8 {
+        (self.pins.e2.is_some() && row >= 2) as u8
+    }
+
+    async fn set_ddram_address(&mut self, address: u8) -> Result<(), Error<E>> {
+        self.output.rs = 0;
+        self.output.rw = 0;
+
+        self.lcd_write(0x80 | address, false).await?;
+        self.instruction_delay(37).await;
+        self.address = address;
 
         Ok(())
     }
 
-    pub async fn create_char(&mut self, location: u8, charmap: &[u8; 8]) -> Result<(), E> {
+    /// Programs `slot` with `charmap` (one byte per row, top first, low 5
+    /// bits shown) and moves the cursor back to the DDRAM: to (0, 0), or
+    /// where it was with `LcdConfig::preserve_cursor`
+    pub async fn create_char(
+        &mut self,
+        slot: CgramSlot,
+        charmap: &[u8; 8],
+    ) -> Result<(), Error<E>> {
+        // Prefer the real address counter when reads are known to work
+        let previous_address = match (self.config.preserve_cursor, self.busy_polling) {
+            (true, true) => Some(self.read_address().await?),
+            (true, false) => Some(self.address),
+            (false, _) => None,
+        };
+
+        self.set_cgram(slot, charmap).await?;
+
+        // Set the address pointer back to the DDRAM
+        match previous_address {
+            Some(address) => self.set_ddram_address(address).await?,
+            None => self.set_cursor(0, 0).await?,
+        }
+        Ok(())
+    }
+
+    /// Programs a glyph like `create_char` but leaves the address counter in
+    /// CGRAM, so several glyphs can be defined before a single `set_cursor`
+    /// (which is needed before writing text again)
+    pub async fn set_cgram(&mut self, slot: CgramSlot, charmap: &[u8; 8]) -> Result<(), Error<E>> {
         self.output.rs = 0;
         self.output.rw = 0;
 
-        let location = location % 8;
+        match slot.index() {
+            index if index < FULL_BLOCK_SLOT => self.big_digit_glyphs = false,
+            FULL_BLOCK_SLOT => self.full_block_glyph = false,
+            _ => self.bar_glyphs = false,
+        }
 
-        self.lcd_write(0x40 | (location << 3), false).await?;
-        Timer::after_micros(37).await;
+        self.lcd_write(0x40 | (slot.index() << 3), false).await?;
+        self.instruction_delay(37).await;
 
+        // The DDRAM address is kept for whoever moves the cursor back
+        let address = self.address;
         for &byte in charmap.iter() {
+            // Only the low 5 bits of each row are displayed
+            let byte = if self.config.mask_charmap {
+                byte & 0x1F
+            } else {
+                byte
+            };
             self.write_byte(byte).await?;
         }
+        self.address = address;
+        Ok(())
+    }
+
+    /// Sends a raw instruction (RS=0). The driver's view of the display
+    /// (cursor position, display and entry mode flags) is not updated.
+    pub async fn command(&mut self, byte: u8) -> Result<(), Error<E>> {
+        self.output.rs = 0;
+        self.output.rw = 0;
+
+        self.lcd_write(byte, false).await?;
+        // Clear and return home are the only slow instructions
+        let delay_us = if byte <= 0x03 {
+            self.config.controller.clear_us()
+        } else {
+            37
+        };
+        self.instruction_delay(delay_us).await;
 
-        // Set the address pointer back to the DDRAM
-        self.set_cursor(0, 0).await?;
         Ok(())
     }
 
-    pub async fn write_byte(&mut self, byte: u8) -> Result<(), E> {
+    /// Sends a raw data byte (RS=1) to DDRAM or CGRAM, same as `write_byte`
+    pub async fn data(&mut self, byte: u8) -> Result<(), Error<E>> {
+        self.write_byte(byte).await
+    }
+
+    /// Prints the glyph stored by `create_char` in the same slot
+    pub async fn write_custom(&mut self, slot: CgramSlot) -> Result<(), Error<E>> {
+        self.write_byte(slot.index()).await
+    }
+
+    /// Writes one display code at the cursor and waits for it to land
+    pub async fn write_byte(&mut self, byte: u8) -> Result<(), Error<E>> {
+        self.write_byte_fast(byte).await?;
+        self.instruction_delay(41).await;
+        Ok(())
+    }
+
+    /// `write_byte` without the 41us wait, for when the bus is slower than the
+    /// display anyway: four port writes at 100kHz already take longer than
+    /// that. With busy polling enabled `write_byte` skips the wait itself.
+    pub async fn write_byte_fast(&mut self, byte: u8) -> Result<(), Error<E>> {
         self.output.rs = 1;
         self.output.rw = 0;
 
         self.lcd_write(byte, false).await?;
-        Timer::after_micros(41).await;
+        #[cfg(feature = "buffered")]
+        if !self.cgram {
+            self.cache_byte(byte);
+        }
+        // Autoscroll shifts the display along with DDRAM writes
+        if !self.cgram && self.entry_state & 1 != 0 {
+            self.shift_display(self.entry_state & (1 << 1) != 0);
+        }
+        self.advance_address();
 
         Ok(())
     }
 
-    pub async fn write_str(&mut self, s: &str) -> Result<(), E> {
-        for byte in s.bytes() {
+    // Mirrors the HD44780 address counter after a data read or write. In
+    // two-line mode the lines occupy 0x00-0x27 and 0x40-0x67 and the counter
+    // jumps between them; in one-line mode it runs through 0x00-0x4F.
+    fn advance_address(&mut self) {
+        self.step_address(self.entry_state & (1 << 1) != 0);
+    }
+
+    fn step_address(&mut self, increment: bool) {
+        self.address = match (self.config.lines, increment) {
+            (Lines::Two, true) => match self.address {
+                0x27 => 0x40,
+                0x67 => 0x00,
+                address => address + 1,
+            },
+            (Lines::Two, false) => match self.address {
+                0x40 => 0x27,
+                0x00 => 0x67,
+                address => address - 1,
+            },
+            (Lines::One, true) if self.address >= 0x4F => 0x00,
+            (Lines::One, true) => self.address + 1,
+            (Lines::One, false) if self.address == 0x00 => 0x4F,
+            (Lines::One, false) => self.address - 1,
+        };
+    }
+
+    /// Characters are translated for the configured ROM, with '?' standing in
+    /// for anything it cannot show. '\n' moves to the start of the next row
+    /// (wrapping to the top) and '\r' to the start of the current one. Use
+    /// `write_byte` to send raw character codes.
+    pub async fn write_str(&mut self, s: &str) -> Result<(), Error<E>> {
+        for c in s.chars() {
+            self.write_char(c).await?;
+        }
+        Ok(())
+    }
+
+    /// Writes one character as a single display code, handled like `write_str`
+    pub async fn write_char(&mut self, c: char) -> Result<(), Error<E>> {
+        match c {
+            '\n' => {
+                let (_, row) = self.position();
+                let next_row = (row + 1) % self.config.dimensions.rows.clamp(1, 4);
+                self.set_cursor(0, next_row).await
+            }
+            '\r' => {
+                let (_, row) = self.position();
+                self.set_cursor(0, row).await
+            }
+            c => self.write_byte(self.config.rom.encode(c)).await,
+        }
+    }
+
+    /// Blanks `row` and leaves the cursor at its start, which is the right
+    /// end when writing right to left
+    pub async fn clear_line(&mut self, row: u8) -> Result<(), Error<E>> {
+        let cols = self.config.dimensions.cols;
+        let start = self.field_start(0, cols);
+        self.set_cursor(start, row).await?;
+        self.write_repeated(b' ', cols as usize).await?;
+        self.set_cursor(start, row).await
+    }
+
+    /// Writes the display code `byte` `count` times from the cursor, e.g. to
+    /// fill a region or draw a bar from a custom glyph
+    pub async fn write_repeated(&mut self, byte: u8, count: usize) -> Result<(), Error<E>> {
+        for _ in 0..count {
+            self.write_byte(byte).await?;
+        }
+        Ok(())
+    }
+
+    /// Raw display codes, so custom glyphs 0-7 can be mixed with text
+    pub async fn write_bytes(&mut self, data: &[u8]) -> Result<(), Error<E>> {
+        for &byte in data {
             self.write_byte(byte).await?;
         }
         Ok(())
     }
 
-    async fn lcd_write(&mut self, output: u8, initialization: bool) -> Result<(), E> {
+    /// Decimal, without padding
+    pub async fn write_u32(&mut self, value: u32) -> Result<(), Error<E>> {
+        let mut buffer = [0; 10];
+        for &digit in format_u32(value, &mut buffer) {
+            self.write_byte(digit).await?;
+        }
+        Ok(())
+    }
+
+    /// Right-aligns `value` in a field of `width` cells filled with `fill`.
+    /// Values with more digits than `width` are written in full.
+    pub async fn write_u32_padded(
+        &mut self,
+        value: u32,
+        width: u8,
+        fill: u8,
+    ) -> Result<(), Error<E>> {
+        let mut buffer = [0; 10];
+        let digits = format_u32(value, &mut buffer);
+
+        for _ in digits.len()..width as usize {
+            self.write_byte(fill).await?;
+        }
+        for &digit in digits {
+            self.write_byte(digit).await?;
+        }
+        Ok(())
+    }
+
+    /// Decimal with a leading `-` for negative values
+    pub async fn write_i32(&mut self, value: i32) -> Result<(), Error<E>> {
+        if value < 0 {
+            self.write_byte(b'-').await?;
+        }
+        self.write_u32(value.unsigned_abs()).await
+    }
+
+    /// Text that would run past the last column is dropped instead of spilling
+    /// into whatever DDRAM address follows the row. Returns how many
+    /// characters were written.
+    pub async fn write_str_at(&mut self, col: u8, row: u8, s: &str) -> Result<usize, Error<E>> {
+        self.set_cursor(col, row).await?;
+        self.write_str_clipped(s).await
+    }
+
+    /// Writes `s` into a `width` column field at (col, row), padding with
+    /// spaces so a shorter value erases what was there. Text longer than the
+    /// field (or the row) is cut off. Right to left, `s` starts at the right
+    /// end of the field.
+    pub async fn write_field(
+        &mut self,
+        col: u8,
+        row: u8,
+        s: &str,
+        width: u8,
+    ) -> Result<(), Error<E>> {
+        let width = width.min(self.config.dimensions.cols.saturating_sub(col));
+        self.set_cursor(self.field_start(col, width), row).await?;
+        let width = width as usize;
+
+        let mut written = 0;
+        for c in s.chars().take(width) {
+            self.write_byte(self.config.rom.encode(c)).await?;
+            written += 1;
+        }
+        self.write_repeated(b' ', width - written).await
+    }
+
+    /// Rewrites the whole screen from one string per row, each padded to the
+    /// full width. Rows without a string are blanked and extra strings are
+    /// ignored.
+    pub async fn write_screen(&mut self, lines: &[&str]) -> Result<(), Error<E>> {
+        let Dimensions { cols, rows } = self.config.dimensions;
+        for row in 0..rows.clamp(1, 4) {
+            let line = lines.get(row as usize).copied().unwrap_or("");
+            self.write_field(0, row, line, cols).await?;
+        }
+        Ok(())
+    }
+
+    /// Writes as much of `s` as fits between the cursor and the edge of its
+    /// row (the left edge when writing right to left) and returns how many
+    /// characters that was. Control characters are not interpreted.
+    pub async fn write_str_clipped(&mut self, s: &str) -> Result<usize, Error<E>> {
+        let (col, _) = self.position();
+        let cols = self.config.dimensions.cols;
+        let available = match self.text_direction() {
+            Direction::LeftToRight => cols.saturating_sub(col),
+            Direction::RightToLeft if col < cols => col + 1,
+            Direction::RightToLeft => 0,
+        };
+
+        let mut written = 0;
+        for c in s.chars().take(available as usize) {
+            self.write_byte(self.config.rom.encode(c)).await?;
+            written += 1;
+        }
+        Ok(written)
+    }
+
+    /// Like `write_str`, but text reaching the end of a row continues at the
+    /// start of the next one, and anything past the bottom-right cell is
+    /// dropped. '\n' also moves to the next row. Assumes left-to-right entry.
+    pub async fn write_wrapped(&mut self, s: &str) -> Result<(), Error<E>> {
+        let cols = self.config.dimensions.cols;
+        let rows = self.config.dimensions.rows.clamp(1, 4);
+        let (mut col, mut row) = self.position();
+
+        for c in s.chars() {
+            if c == '\r' {
+                col = 0;
+                self.set_cursor(0, row).await?;
+                continue;
+            }
+            if c == '\n' || col >= cols {
+                row += 1;
+                col = 0;
+                if row >= rows {
+                    break;
+                }
+                self.set_cursor(0, row).await?;
+                if c == '\n' {
+                    continue;
+                }
+            }
+
+            self.write_byte(self.config.rom.encode(c)).await?;
+            col += 1;
+        }
+        Ok(())
+    }
+
+    /// Marquee: fills `row` with the window of `text` that starts `offset`
+    /// characters in, looping back to the start past the end. Pad the text
+    /// with spaces for a gap between repetitions, and advance `offset` on a
+    /// timer to scroll.
+    pub async fn scroll_text_step(
+        &mut self,
+        text: &str,
+        row: u8,
+        offset: usize,
+    ) -> Result<(), Error<E>> {
+        let len = text.chars().count();
+        if len == 0 {
+            return self.clear_line(row).await;
+        }
+
+        let cols = self.config.dimensions.cols;
+        self.set_cursor(self.field_start(0, cols), row).await?;
+        for c in text.chars().cycle().skip(offset % len).take(cols as usize) {
+            self.write_byte(self.config.rom.encode(c)).await?;
+        }
+        Ok(())
+    }
+
+    /// Rewrites `row` with `s` (padded with spaces) only if that differs from
+    /// what the row is known to show, and returns whether anything was sent.
+    /// Writes through the other methods keep the cache up to date; assumes
+    /// left-to-right entry mode.
+    #[cfg(feature = "buffered")]
+    pub async fn update_line(&mut self, row: u8, s: &str) -> Result<bool, Error<E>> {
+        if row >= self.config.dimensions.rows.min(4) {
+            return Err(Error::InvalidCursor);
+        }
+
+        let cols = self.config.dimensions.cols.min(40) as usize;
+        let mut line = [b' '; 40];
+        for (cell, c) in line[..cols].iter_mut().zip(s.chars()) {
+            *cell = self.config.rom.encode(c);
+        }
+
+        let known = self.lines_known & (1 << row) != 0;
+        if known && self.lines[row as usize][..cols] == line[..cols] {
+            return Ok(false);
+        }
+
+        self.set_cursor(0, row).await?;
+        self.write_bytes(&line[..cols]).await?;
+        self.lines_known |= 1 << row;
+        Ok(true)
+    }
+
+    #[cfg(feature = "buffered")]
+    fn cache_byte(&mut self, byte: u8) {
+        let (col, row) = self.position();
+        if col < self.config.dimensions.cols.min(40) && row < 4 {
+            self.lines[row as usize][col as usize] = byte;
+        }
+    }
+
+    /// Draws a bar `width` cells wide at (col, row), filled to `percent`
+    /// (0-100) to the nearest fifth of a cell. A bar running past the edge of
+    /// the row is shortened to fit. The partial cell glyphs take CGRAM slots
+    /// 4-7 and are programmed on first use.
+    pub async fn progress_bar(
+        &mut self,
+        col: u8,
+        row: u8,
+        width: u8,
+        percent: u8,
+    ) -> Result<(), Error<E>> {
+        if !self.bar_glyphs {
+            for columns in 1..=4 {
+                let slot = CgramSlot::ALL[(BAR_FIRST_SLOT + columns - 1) as usize];
+                let row_bits = (0x1F << (5 - columns)) & 0x1F;
+                self.create_char(slot, &[row_bits; 8]).await?;
+            }
+            self.bar_glyphs = true;
+        }
+        let full_block = self.full_block().await?;
+
+        let width = width.min(self.config.dimensions.cols.saturating_sub(col));
+        let units = width as u32 * 5;
+        let filled = (percent.min(100) as u32 * units + 50) / 100;
+        let full = (filled / 5) as usize;
+        let partial = (filled % 5) as u8;
+        let empty = width as usize - full - (partial > 0) as usize;
+
+        // The bar fills from the left either way, so right to left it is
+        // drawn starting with the empty end
+        self.set_cursor(self.field_start(col, width), row).await?;
+        if self.text_direction() == Direction::LeftToRight {
+            self.write_repeated(full_block, full).await?;
+        } else {
+            self.write_repeated(b' ', empty).await?;
+        }
+        if partial > 0 {
+            self.write_byte(BAR_FIRST_SLOT + partial - 1).await?;
+        }
+        if self.text_direction() == Direction::LeftToRight {
+            self.write_repeated(b' ', empty).await
+        } else {
+            self.write_repeated(full_block, full).await
+        }
+    }
+
+    // Code of a solid cell: the ROM's where it has one, otherwise a glyph in
+    // slot 3 programmed on first use
+    async fn full_block(&mut self) -> Result<u8, Error<E>> {
+        if let Some(code) = self.config.rom.full_block() {
+            return Ok(code);
+        }
+        if !self.full_block_glyph {
+            self.create_char(CgramSlot::ALL[FULL_BLOCK_SLOT as usize], &[0x1F; 8])
+                .await?;
+            self.full_block_glyph = true;
+        }
+        Ok(FULL_BLOCK_SLOT)
+    }
+
+    // Column to start writing a `width` column field at `col` from, which is
+    // its right end when writing right to left
+    fn field_start(&self, col: u8, width: u8) -> u8 {
+        match self.text_direction() {
+            Direction::LeftToRight => col,
+            Direction::RightToLeft => col.saturating_add(width.saturating_sub(1)),
+        }
+    }
+
+    /// Draws `digit` three columns wide and two rows tall with its top-left
+    /// corner at (col, row), e.g. for a clock. Anything other than 0-9 draws
+    /// blanks, which erases a digit. The glyphs take CGRAM slots 0-2 and are
+    /// programmed on first use.
+    pub async fn write_big_digit(&mut self, digit: u8, col: u8, row: u8) -> Result<(), Error<E>> {
+        let dimensions = self.config.dimensions;
+        if col as u16 + 3 > dimensions.cols as u16 || row as u16 + 2 > dimensions.rows as u16 {
+            return Err(Error::InvalidCursor);
+        }
+
+        if !self.big_digit_glyphs {
+            for (slot, charmap) in CgramSlot::ALL.iter().zip(&BIG_DIGIT_GLYPHS) {
+                self.create_char(*slot, charmap).await?;
+            }
+            self.big_digit_glyphs = true;
+        }
+        let full_block = self.full_block().await?;
+
+        let cells = BIG_DIGITS
+            .get(digit as usize)
+            .copied()
+            .unwrap_or([[b' '; 3]; 2]);
+        for (line, mut codes) in (row..).zip(cells) {
+            if self.text_direction() == Direction::RightToLeft {
+                codes.reverse();
+            }
+            for code in codes.iter_mut().filter(|code| **code == charset::F) {
+                *code = full_block;
+            }
+            self.set_cursor(self.field_start(col, 3), line).await?;
+            self.write_bytes(&codes).await?;
+        }
+        Ok(())
+    }
+
+    // Synchronous entry point for the formatting traits. Blocks until the async
+    // write (including the per-character delay) has completed, plus the
+    // configured `fmt_char_delay_us` after each character.
+    #[cfg(feature = "fmt")]
+    fn write_str_blocking(&mut self, s: &str) -> Result<(), Error<E>> {
+        block_on(async {
+            for c in s.chars() {
+                self.write_char(c).await?;
+                if self.config.fmt_char_delay_us > 0 {
+                    self.delay.delay_us(self.config.fmt_char_delay_us).await;
+                }
+            }
+            Ok(())
+        })
+    }
+
+    async fn lcd_write(&mut self, output: u8, initialization: bool) -> Result<(), Error<E>> {
         self.output.data = output;
+        self.output.enables = self.write_enables(output);
+
+        let eight_bit = self.config.interface == Interface::EightBit;
+        if self.config.batch_writes && !eight_bit {
+            return self.lcd_write_batched(initialization).await;
+        }
+
+        // Let RS/RW settle before raising E
+        if self.config.setup_us > 0 {
+            self.output.e = 0;
+            self.i2c_write(self.output.get_high_data(&self.pins))
+                .await?;
+            self.delay.delay_us(self.config.setup_us).await;
+        }
 
         // Send high nibble
         self.output.e = 1;
-        self.i2c_write(self.output.get_high_data())?;
-        Timer::after_micros(1).await;
+        self.i2c_write(self.output.get_high_data(&self.pins))
+            .await?;
+        self.delay.delay_us(self.config.enable_pulse_us).await;
 
         self.output.e = 0;
-        self.i2c_write(self.output.get_high_data())?;
+        self.i2c_write(self.output.get_high_data(&self.pins))
+            .await?;
 
-        // During initialization we only send half a byte
-        if !initialization {
-            Timer::after_micros(37).await;
+        // During initialization we only send half a byte, and the 8-bit
+        // interface has already sent all of it
+        if !initialization && !eight_bit {
+            self.delay.delay_us(self.config.nibble_settle_us).await;
 
             // Send low nibble
             self.output.e = 1;
-            self.i2c_write(self.output.get_low_data())?;
-            Timer::after_micros(1).await;
+            self.i2c_write(self.output.get_low_data(&self.pins)).await?;
+            self.delay.delay_us(self.config.enable_pulse_us).await;
 
             self.output.e = 0;
-            self.i2c_write(self.output.get_low_data())?;
+            self.i2c_write(self.output.get_low_data(&self.pins)).await?;
+        }
+
+        if !initialization && self.busy_polling {
+            self.wait_ready().await?;
         }
 
         Ok(())
     }
 
-    fn i2c_write(&mut self, output: u8) -> Result<(), E> {
-        self.i2c.write(self.address, &[TCA9534_REG_OUTPUT, output])
+    /// Raw control of the port, for debugging or protocols the driver does
+    /// not implement. The setters only change the pending state; `commit`
+    /// writes it out. The driver's own tracking does not see any of this.
+    pub fn set_rs(&mut self, high: bool) {
+        self.output.rs = high as u8;
     }
-}
 
-impl<I2C, E> fmt::Write for LcdI2c<I2C>
-where
-    I2C: I2c<Error = E>,
-{
-    fn write_str(&mut self, s: &str) -> fmt::Result {
-        for byte in s.bytes() {
-            self.output.rs = 1;
-            self.output.rw = 0;
-            self.output.data = byte;
+    /// Pending level of RW, see `set_rs`
+    pub fn set_rw(&mut self, high: bool) {
+        self.output.rw = high as u8;
+    }
 
-            // High nibble
-            self.output.e = 1;
-            if self.i2c_write(self.output.get_high_data()).is_err() {
-                return Err(fmt::Error);
+    /// Pending level of E, see `set_rs`
+    pub fn set_enable(&mut self, high: bool) {
+        self.output.e = high as u8;
+    }
+
+    /// D4-D7 from the low 4 bits of `nibble`
+    pub fn set_data(&mut self, nibble: u8) {
+        self.output.data = nibble << 4;
+    }
+
+    /// Writes the pending RS, RW, E and D4-D7 levels in one port write
+    pub async fn commit(&mut self) -> Result<(), Error<E>> {
+        self.i2c_write(self.output.get_high_data(&self.pins)).await
+    }
+
+    /// Reads the busy flag once; fails with `Error::Unsupported` on
+    /// write-only wiring
+    pub async fn is_ready(&mut self) -> Result<bool, Error<E>> {
+        self.output.rs = 0;
+
+        let status = self.lcd_read().await?;
+        Ok(status & 0x80 == 0)
+    }
+
+    /// Reads the address counter (bits 0-6); needs readable RW and D4-D7
+    pub async fn read_address(&mut self) -> Result<u8, Error<E>> {
+        self.output.rs = 0;
+
+        let status = self.lcd_read().await?;
+        Ok(status & 0x7F)
+    }
+
+    /// Reads the DDRAM/CGRAM byte at the address counter, which then advances
+    /// like it does for writes
+    pub async fn read_byte(&mut self) -> Result<u8, Error<E>> {
+        self.output.rs = 1;
+
+        let byte = self.lcd_read().await?;
+        // Reads are not followed by a busy-flag poll, so always wait
+        self.delay.delay_us(41).await;
+        self.advance_address();
+
+        Ok(byte)
+    }
+
+    // Sends the E high/low states of both nibbles as a single transaction.
+    // Each byte is latched onto the port as it is acknowledged, so E stays
+    // high for one byte time on the bus (~22us at 400kHz, ~90us at 100kHz),
+    // well above the 450ns minimum. The nibbles are no longer separated by a
+    // fixed delay; only the post-instruction delays remain.
+    async fn lcd_write_batched(&mut self, initialization: bool) -> Result<(), Error<E>> {
+        let mut sequence = [0; 4];
+        let mut len = 0;
+
+        let nibbles = if initialization { 1 } else { 2 };
+        for nibble in 0..nibbles {
+            for e in [1, 0] {
+                self.output.e = e;
+                sequence[len] = if nibble == 0 {
+                    self.output.get_high_data(&self.pins)
+                } else {
+                    self.output.get_low_data(&self.pins)
+                };
+                len += 1;
             }
+        }
 
-            self.output.e = 0;
-            if self.i2c_write(self.output.get_high_data()).is_err() {
-                return Err(fmt::Error);
+        self.ensure_connected()?;
+        let result = self.expander.write_port_sequence(&sequence[..len]).await;
+        self.track(result)?;
+
+        if !initialization && self.busy_polling {
+            self.wait_ready().await?;
+        }
+
+        Ok(())
+    }
+
+    // Follows whether data goes to DDRAM or CGRAM. With two controllers,
+    // instructions are latched by both at once except for DDRAM addressing.
+    // Data goes to the controller holding the cursor, or to both while CGRAM
+    // is being written.
+    fn write_enables(&mut self, output: u8) -> u8 {
+        let broadcast = if self.output.rs == 1 {
+            self.cgram
+        } else {
+            match output {
+                0x80.. => {
+                    self.cgram = false;
+                    false
+                }
+                0x40..=0x7F => {
+                    self.cgram = true;
+                    true
+                }
+                0x01..=0x03 => {
+                    self.cgram = false;
+                    true
+                }
+                _ => true,
             }
+        };
 
-            // Low nibble
-            self.output.e = 1;
-            if self.i2c_write(self.output.get_low_data()).is_err() {
-                return Err(fmt::Error);
+        match (self.pins.e2, broadcast) {
+            (None, _) => 0b01,
+            (Some(_), true) => 0b11,
+            (Some(_), false) => 1 << self.controller,
+        }
+    }
+
+    // Execution time of the instruction just sent. Skipped when busy polling,
+    // as `lcd_write` has already waited for the busy flag to clear.
+    async fn instruction_delay(&mut self, us: u32) {
+        if !self.busy_polling {
+            self.delay.delay_us(us).await;
+        }
+    }
+
+    async fn wait_ready(&mut self) -> Result<(), Error<E>> {
+        let rs = self.output.rs;
+        self.output.rs = 0;
+
+        let mut result = Err(Error::Timeout);
+        for _ in 0..self.config.busy_poll_limit.max(1) {
+            match self.lcd_read().await {
+                // Bit 7 is the busy flag
+                Ok(status) if status & 0x80 != 0 => continue,
+                other => {
+                    result = other.map(|_| ());
+                    break;
+                }
             }
+        }
 
-            self.output.e = 0;
-            if self.i2c_write(self.output.get_low_data()).is_err() {
-                return Err(fmt::Error);
+        if let Err(Error::Timeout) = result {
+            self.busy_timeouts = self.busy_timeouts.saturating_add(1);
+        }
+
+        self.output.rs = rs;
+        result
+    }
+
+    async fn lcd_read(&mut self) -> Result<u8, Error<E>> {
+        if !self.can_read() {
+            return Err(Error::Unsupported);
+        }
+
+        // Release D4-D7 so the LCD can drive them. Only one controller may
+        // drive the bus at a time.
+        self.output.rw = 1;
+        self.output.data = 0xFF;
+        self.output.enables = 1 << self.controller;
+        self.set_data_input(true).await?;
+
+        let value = match self.config.interface {
+            Interface::FourBit => {
+                let high = self.read_nibble().await;
+                let low = self.read_nibble().await;
+                high.and_then(|high| Ok((high << 4) | low?))
             }
+            Interface::EightBit => self.read_data_port().await,
+        };
+
+        self.output.rw = 0;
+        self.i2c_write(self.output.get_high_data(&self.pins))
+            .await?;
+        self.set_data_input(false).await?;
+
+        value
+    }
+
+    async fn set_data_input(&mut self, input: bool) -> Result<(), Error<E>> {
+        if !self.expander.has_data_direction() {
+            return Ok(());
         }
-        Ok(())
+        self.ensure_connected()?;
+        let result = self.expander.set_data_input(input).await;
+        self.track(result)
+    }
+
+    async fn read_data_port(&mut self) -> Result<u8, Error<E>> {
+        self.output.e = 1;
+        self.i2c_write(self.output.get_high_data(&self.pins))
+            .await?;
+        self.delay.delay_us(self.config.enable_pulse_us).await;
+
+        let result = self.expander.read_data_port().await;
+        let data = self.track(result)?;
+
+        self.output.e = 0;
+        self.i2c_write(self.output.get_high_data(&self.pins))
+            .await?;
+
+        Ok(data)
+    }
+
+    async fn read_nibble(&mut self) -> Result<u8, Error<E>> {
+        self.output.e = 1;
+        self.i2c_write(self.output.get_high_data(&self.pins))
+            .await?;
+        self.delay.delay_us(self.config.enable_pulse_us).await;
+
+        let result = self.expander.read_port().await;
+        let port = self.track(result)?;
+
+        self.output.e = 0;
+        self.i2c_write(self.output.get_high_data(&self.pins))
+            .await?;
+
+        Ok((port >> self.pins.data) & 0x0F)
+    }
+
+    // Writes the control port, and with the 8-bit interface the current data
+    // byte on the data port alongside it
+    async fn i2c_write(&mut self, output: u8) -> Result<(), Error<E>> {
+        self.ensure_connected()?;
+        let result = match self.config.interface {
+            Interface::FourBit => self.expander.write_port(output).await,
+            Interface::EightBit => {
+                let data = self.output.data;
+                self.expander.write_ports(output, data).await
+            }
+        };
+        self.track(result)
+    }
+
+    // Reading drives RW high, which would strobe a second controller whose
+    // E shares the RW pin
+    fn can_read(&self) -> bool {
+        self.config.read_capability == ReadCapability::ReadWrite
+            && self.expander.can_read()
+            && self.pins.e2 != Some(self.pins.rw)
+    }
+
+    fn ensure_connected(&self) -> Result<(), Error<E>> {
+        if self.misconfigured {
+            Err(Error::InvalidConfig)
+        } else if self.disconnected {
+            Err(Error::Disconnected)
+        } else {
+            Ok(())
+        }
+    }
+
+    // Counts consecutive bus errors and marks the display disconnected once
+    // the configured limit is reached
+    fn track<T>(&mut self, result: Result<T, E>) -> Result<T, Error<E>> {
+        match result {
+            Ok(value) => {
+                self.failures = 0;
+                Ok(value)
+            }
+            Err(e) => {
+                self.failures = self.failures.saturating_add(1);
+                let limit = self.config.disconnect_after;
+                if limit > 0 && self.failures >= limit {
+                    self.disconnected = true;
+                }
+                Err(Error::I2c(e))
+            }
+        }
+    }
+}
+
+/// Shortcuts for the usual PCF8574 backpack at 0x27
+impl<I2C, D, E> LcdI2c<Pcf8574<I2C>, D>
+where
+    I2C: I2cBus<Error = E>,
+    D: DelayNs,
+{
+    /// PCF8574 at 0x27 driving a 16x2 display
+    pub fn default_16x2(i2c: I2C, delay: D) -> Self {
+        Self::new(Pcf8574::new(i2c, 0x27), delay)
+    }
+
+    /// PCF8574 at 0x27 driving a 20x4 display
+    pub fn default_20x4(i2c: I2C, delay: D) -> Self {
+        Self::new_with_size(Pcf8574::new(i2c, 0x27), Dimensions::new(20, 4), delay)
+    }
+}
+
+// Driver state only; the expander and delay are left out so neither needs
+// to implement `Debug`
+impl<EXP, D, E> core::fmt::Debug for LcdI2c<EXP, D>
+where
+    EXP: PortExpander<Error = E>,
+    D: DelayNs,
+{
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("LcdI2c")
+            .field("bus_address", &self.expander.bus_address())
+            .field("ddram_address", &self.address)
+            .field("position", &self.position())
+            .field("display_state", &self.display_state)
+            .field("entry_state", &self.entry_state)
+            .field("connected", &!self.disconnected)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(feature = "fmt")]
+impl<EXP, D, E> fmt::Write for LcdI2c<EXP, D>
+where
+    EXP: PortExpander<Error = E>,
+    D: DelayNs,
+{
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.write_str_blocking(s).map_err(|_| fmt::Error)
     }
 }
@@ -0,0 +1,111 @@
+mod common;
+
+use common::{MockI2c, RecordingDelay};
+use lcd_i2c::{Error, LcdConfig, LcdI2cBlocking, NoDelay, Pcf8574, ReadCapability, TimingMode};
+
+#[test]
+fn stuck_busy_flag_times_out_and_is_counted() {
+    // Every port read has D7 high, i.e. the display never becomes ready
+    let mut bus = MockI2c {
+        reads: vec![0xF0; 16],
+        ..Default::default()
+    };
+    let config = LcdConfig::new().busy_poll_limit(3);
+    let mut lcd =
+        LcdI2cBlocking::new_with_config(Pcf8574::new(&mut bus, 0x27), config, NoDelay).unwrap();
+    lcd.use_busy_flag(true);
+
+    assert_eq!(lcd.clear(), Err(Error::Timeout));
+    assert_eq!(lcd.busy_timeouts(), 1);
+
+    // Three polls of two nibble reads each
+    assert_eq!(bus.reads.len(), 16 - 6);
+}
+
+#[test]
+fn write_only_wiring_rejects_reads_and_keeps_fixed_delays() {
+    let mut bus = MockI2c::default();
+    let config = LcdConfig::new().read_capability(ReadCapability::WriteOnly);
+    let mut lcd =
+        LcdI2cBlocking::new_with_config(Pcf8574::new(&mut bus, 0x27), config, NoDelay).unwrap();
+
+    lcd.set_timing_mode(TimingMode::Busy);
+    assert_eq!(lcd.timing_mode(), TimingMode::FixedDelay);
+    assert_eq!(lcd.read_address(), Err(Error::Unsupported));
+    assert_eq!(lcd.read_byte(), Err(Error::Unsupported));
+    assert_eq!(lcd.is_ready(), Err(Error::Unsupported));
+
+    assert!(bus.writes.is_empty());
+}
+
+#[test]
+fn verified_begin_fails_if_the_display_stays_busy() {
+    let mut bus = MockI2c {
+        reads: vec![0xF0; 8],
+        ..Default::default()
+    };
+    let config = LcdConfig::new().busy_poll_limit(4).verify_init(true);
+    let mut lcd =
+        LcdI2cBlocking::new_with_config(Pcf8574::new(&mut bus, 0x27), config, NoDelay).unwrap();
+
+    assert_eq!(lcd.begin(), Err(Error::InitFailed));
+    // Four polls used up the busy reads; the mock then reads 0 (ready)
+    lcd.begin().unwrap();
+    assert!(lcd.is_ready().unwrap());
+}
+
+#[test]
+fn busy_mode_polls_instead_of_waiting_out_the_fixed_delays() {
+    let mut bus = MockI2c::default();
+    let delay = RecordingDelay::default();
+    // A short settle time keeps the nibble gap apart from the 37us delay
+    let config = LcdConfig::new().nibble_settle_us(2);
+    let mut lcd =
+        LcdI2cBlocking::new_with_config(Pcf8574::new(&mut bus, 0x27), config, delay.clone())
+            .unwrap();
+
+    lcd.clear().unwrap();
+    lcd.display().unwrap();
+    assert!(delay.0.borrow().contains(&1_600_000));
+    assert!(delay.0.borrow().contains(&37_000));
+
+    // The mock reads back 0, so every poll finds the display ready
+    lcd.set_timing_mode(TimingMode::Busy);
+    delay.0.borrow_mut().clear();
+    lcd.clear().unwrap();
+    lcd.display().unwrap();
+    assert!(!delay.0.borrow().contains(&1_600_000));
+    assert!(!delay.0.borrow().contains(&37_000));
+}
+
+#[test]
+fn read_address_joins_the_two_nibble_reads() {
+    // Busy flag set, address 0x45; the low port bits are not data
+    let mut bus = MockI2c {
+        reads: vec![0xC7, 0x5B],
+        ..Default::default()
+    };
+    let mut lcd = LcdI2cBlocking::new(Pcf8574::new(&mut bus, 0x27), NoDelay);
+    assert_eq!(lcd.read_address(), Ok(0x45));
+    assert!(bus.reads.is_empty());
+}
+
+#[test]
+fn read_byte_joins_the_two_nibble_reads_with_rs_high() {
+    let mut bus = MockI2c {
+        reads: vec![0x4A, 0x1F],
+        ..Default::default()
+    };
+    let mut lcd = LcdI2cBlocking::new(Pcf8574::new(&mut bus, 0x27), NoDelay);
+    assert_eq!(lcd.read_byte(), Ok(b'A'));
+    assert!(bus.reads.is_empty());
+
+    // RS and RW stay up until the read is over, then RW drops
+    let writes = bus.port_writes();
+    let (last, reading) = writes.split_last().unwrap();
+    assert!(
+        reading.iter().all(|port| port & 0x03 == 0x03),
+        "{writes:x?}"
+    );
+    assert_eq!(last & 0x03, 0x01);
+}
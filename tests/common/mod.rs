@@ -0,0 +1,172 @@
+#![allow(dead_code)]
+
+use std::cell::RefCell;
+use std::future::poll_fn;
+use std::pin::pin;
+use std::rc::Rc;
+use std::task::{Context, Poll, Waker};
+
+use embedded_hal::delay::DelayNs;
+use embedded_hal::i2c::{ErrorKind, ErrorType, I2c, NoAcknowledgeSource, Operation};
+use embedded_hal::spi::{self, SpiDevice};
+
+// Records every write and answers reads from a queue (zeros once empty).
+// The next `nacks` transactions fail as if nothing was attached.
+#[derive(Default)]
+pub struct MockI2c {
+    pub writes: Vec<(u8, Vec<u8>)>,
+    pub reads: Vec<u8>,
+    pub nacks: usize,
+    pub transactions: usize,
+}
+
+impl MockI2c {
+    // Port states written to a PCF8574, which takes single-byte writes
+    pub fn port_writes(&self) -> Vec<u8> {
+        self.writes
+            .iter()
+            .flat_map(|(_, bytes)| bytes.iter().copied())
+            .collect()
+    }
+
+    // Instruction and data bytes the LCD received, reassembled from the
+    // four port writes per byte of the default pin map
+    pub fn lcd_bytes(&self) -> Vec<u8> {
+        self.port_writes()
+            .chunks(4)
+            .map(|w| (w[0] & 0xF0) | (w[2] >> 4))
+            .collect()
+    }
+}
+
+impl ErrorType for MockI2c {
+    type Error = ErrorKind;
+}
+
+impl I2c for MockI2c {
+    fn transaction(
+        &mut self,
+        address: u8,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), ErrorKind> {
+        self.transactions += 1;
+        if self.nacks > 0 {
+            self.nacks -= 1;
+            return Err(ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address));
+        }
+
+        for operation in operations {
+            match operation {
+                Operation::Write(bytes) => self.writes.push((address, bytes.to_vec())),
+                Operation::Read(buffer) => {
+                    for byte in buffer.iter_mut() {
+                        *byte = if self.reads.is_empty() {
+                            0
+                        } else {
+                            self.reads.remove(0)
+                        };
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+// Async view of a blocking mock for `AsyncI2c` and `AsyncSpi`. Every
+// transaction returns `Pending` once before running, like a bus driven by
+// interrupts.
+pub struct AsyncMock<T>(pub T);
+
+impl<T: ErrorType> ErrorType for AsyncMock<T> {
+    type Error = T::Error;
+}
+
+impl<T: I2c> embedded_hal_async::i2c::I2c for AsyncMock<T> {
+    async fn transaction(
+        &mut self,
+        address: u8,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        yield_once().await;
+        self.0.transaction(address, operations)
+    }
+}
+
+impl<T: spi::ErrorType> spi::ErrorType for AsyncMock<T> {
+    type Error = T::Error;
+}
+
+impl<T: SpiDevice> embedded_hal_async::spi::SpiDevice for AsyncMock<T> {
+    async fn transaction(
+        &mut self,
+        operations: &mut [spi::Operation<'_, u8>],
+    ) -> Result<(), Self::Error> {
+        yield_once().await;
+        self.0.transaction(operations)
+    }
+}
+
+async fn yield_once() {
+    let mut yielded = false;
+    poll_fn(|cx| {
+        if yielded {
+            Poll::Ready(())
+        } else {
+            yielded = true;
+            cx.waker().wake_by_ref();
+            Poll::Pending
+        }
+    })
+    .await
+}
+
+// Records every SPI transaction as the bytes clocked out
+#[derive(Default)]
+pub struct MockSpi {
+    pub writes: Vec<Vec<u8>>,
+}
+
+impl spi::ErrorType for MockSpi {
+    type Error = spi::ErrorKind;
+}
+
+impl SpiDevice for MockSpi {
+    fn transaction(
+        &mut self,
+        operations: &mut [spi::Operation<'_, u8>],
+    ) -> Result<(), spi::ErrorKind> {
+        for operation in operations {
+            match operation {
+                spi::Operation::Write(bytes) => self.writes.push(bytes.to_vec()),
+                spi::Operation::TransferInPlace(buffer) => {
+                    self.writes.push(buffer.to_vec());
+                    buffer.fill(0);
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+// Keeps every requested delay in nanoseconds, shared so the test can look
+// at it while the driver owns the delay.
+#[derive(Clone, Default)]
+pub struct RecordingDelay(pub Rc<RefCell<Vec<u32>>>);
+
+impl DelayNs for RecordingDelay {
+    fn delay_ns(&mut self, ns: u32) {
+        self.0.borrow_mut().push(ns);
+    }
+}
+
+// Runs an async driver call to completion. The mocks never return
+// `Pending`, so a single poll is enough.
+pub fn block_on<F: Future>(future: F) -> F::Output {
+    let mut context = Context::from_waker(Waker::noop());
+    match pin!(future).poll(&mut context) {
+        Poll::Ready(output) => output,
+        Poll::Pending => panic!("mock future returned Pending"),
+    }
+}
@@ -0,0 +1,80 @@
+mod common;
+
+use common::MockI2c;
+use lcd_i2c::{Error, LcdConfig, LcdI2cBlocking, NoDelay, Pcf8574};
+
+#[test]
+fn repeated_nacks_disconnect_until_reconnect() {
+    let mut bus = MockI2c {
+        nacks: 2,
+        ..Default::default()
+    };
+    let config = LcdConfig::new().disconnect_after(2);
+    let mut lcd =
+        LcdI2cBlocking::new_with_config(Pcf8574::new(&mut bus, 0x27), config, NoDelay).unwrap();
+
+    assert!(matches!(lcd.clear(), Err(Error::I2c(_))));
+    assert!(lcd.is_connected());
+    assert!(matches!(lcd.clear(), Err(Error::I2c(_))));
+    assert!(!lcd.is_connected());
+
+    // Fails fast until reconnected
+    assert_eq!(lcd.home(), Err(Error::Disconnected));
+
+    lcd.reconnect().unwrap();
+    assert!(lcd.is_connected());
+    lcd.home().unwrap();
+
+    let (expander, _) = lcd.release();
+    assert!(expander.release().writes.len() > 2);
+}
+
+#[test]
+fn begin_with_retries_recovers_from_a_missed_attempt() {
+    let mut bus = MockI2c {
+        nacks: 1,
+        ..Default::default()
+    };
+    let mut lcd = LcdI2cBlocking::new(Pcf8574::new(&mut bus, 0x27), NoDelay);
+
+    lcd.begin_with_retries(2).unwrap();
+    assert_eq!(bus.transactions, 1 + bus.writes.len());
+}
+
+#[test]
+fn ping_reports_whether_the_expander_acks() {
+    let mut bus = MockI2c {
+        nacks: 1,
+        ..Default::default()
+    };
+    let config = LcdConfig::new().disconnect_after(1);
+    let mut lcd =
+        LcdI2cBlocking::new_with_config(Pcf8574::new(&mut bus, 0x27), config, NoDelay).unwrap();
+
+    assert!(matches!(lcd.ping(), Err(Error::I2c(_))));
+    assert!(!lcd.is_connected());
+
+    // Still answers while disconnected, so the caller knows to reconnect
+    lcd.ping().unwrap();
+    lcd.reconnect().unwrap();
+}
+
+#[test]
+fn begin_with_retries_gives_up_when_the_read_back_keeps_failing() {
+    // Every read says ready but leaves the address counter at 0x55, never
+    // the 0 a successful clear leaves behind
+    let mut bus = MockI2c {
+        reads: vec![0x50; 4096],
+        ..Default::default()
+    };
+    let mut lcd = LcdI2cBlocking::new(Pcf8574::new(&mut bus, 0x27), NoDelay);
+    lcd.use_busy_flag(true);
+
+    assert_eq!(lcd.begin_with_retries(3), Err(Error::InitFailed));
+
+    // One reset sequence per attempt
+    let sync = [0x34, 0x30, 0x34, 0x30, 0x34, 0x30, 0x24, 0x20];
+    let writes = bus.port_writes();
+    let attempts = writes.windows(sync.len()).filter(|w| *w == sync).count();
+    assert_eq!(attempts, 3);
+}
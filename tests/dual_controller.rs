@@ -0,0 +1,117 @@
+mod common;
+
+use common::MockI2c;
+#[cfg(feature = "buffered")]
+use common::block_on;
+use lcd_i2c::{
+    Dimensions, Error, LcdConfig, LcdI2cBlocking, LcdI2cFixed, NoDelay, Pcf8574, PinMap,
+    PortExpander, TimingMode,
+};
+#[cfg(feature = "buffered")]
+use lcd_i2c::{FrameBuffer, LcdI2c};
+
+// 40x4 module with RW tied low and the second controller's E on P1
+struct DualEnable<'a>(Pcf8574<&'a mut MockI2c>);
+
+impl PortExpander for DualEnable<'_> {
+    type Error = embedded_hal::i2c::ErrorKind;
+
+    fn pin_map(&self) -> PinMap {
+        PinMap {
+            e2: Some(1),
+            ..PinMap::DEFAULT
+        }
+    }
+
+    async fn write_port(&mut self, value: u8) -> Result<(), Self::Error> {
+        self.0.write_port(value).await
+    }
+
+    async fn read_port(&mut self) -> Result<u8, Self::Error> {
+        self.0.read_port().await
+    }
+}
+
+fn lcd(bus: &mut MockI2c) -> LcdI2cBlocking<DualEnable<'_>, NoDelay> {
+    let expander = DualEnable(Pcf8574::new(bus, 0x27));
+    LcdI2cBlocking::new_with_size(expander, Dimensions::new(40, 4), NoDelay)
+}
+
+#[test]
+fn instructions_pulse_both_enables() {
+    let mut bus = MockI2c::default();
+    lcd(&mut bus).clear().unwrap();
+
+    assert_eq!(bus.port_writes(), [0x06, 0x00, 0x16, 0x10]);
+}
+
+#[test]
+fn lower_rows_use_the_second_controller() {
+    let mut bus = MockI2c::default();
+    let mut lcd = lcd(&mut bus);
+    lcd.set_cursor(3, 2).unwrap();
+    assert_eq!(lcd.position(), (3, 2));
+    lcd.write_byte(b'A').unwrap();
+
+    // 0x80 | 0x03, then data, both on E2 only
+    assert_eq!(
+        bus.port_writes(),
+        [0x82, 0x80, 0x32, 0x30, 0x43, 0x41, 0x13, 0x11]
+    );
+}
+
+#[test]
+fn upper_rows_use_the_first_controller() {
+    let mut bus = MockI2c::default();
+    lcd(&mut bus).set_cursor(3, 1).unwrap();
+
+    // 0x80 | 0x43
+    assert_eq!(bus.port_writes(), [0xC4, 0xC0, 0x34, 0x30]);
+}
+
+#[test]
+fn fixed_geometry_allows_40x4_with_a_second_controller() {
+    let mut bus = MockI2c::default();
+    let expander = DualEnable(Pcf8574::new(&mut bus, 0x27));
+    let lcd = LcdI2cFixed::<_, _, 40, 4>::new_with_config(expander, LcdConfig::new(), NoDelay);
+    assert_eq!(lcd.unwrap().dimensions(), Dimensions::new(40, 4));
+
+    // A single controller only has DDRAM for 80 cells
+    let lcd = LcdI2cFixed::<_, _, 40, 4>::new_with_config(
+        Pcf8574::new(&mut bus, 0x27),
+        LcdConfig::new(),
+        NoDelay,
+    );
+    assert!(matches!(lcd, Err(Error::InvalidConfig)));
+}
+
+#[cfg(feature = "buffered")]
+#[test]
+fn frame_buffer_keeps_all_40_columns_of_a_40x4() {
+    let mut bus = MockI2c::default();
+    let expander = DualEnable(Pcf8574::new(&mut bus, 0x27));
+    let mut lcd = LcdI2c::new_with_size(expander, Dimensions::new(40, 4), NoDelay);
+    let mut frame = FrameBuffer::new(Dimensions::new(40, 4));
+    block_on(frame.flush(&mut lcd)).unwrap();
+    frame.write_str_at(39, 3, "A");
+    block_on(frame.flush(&mut lcd)).unwrap();
+
+    // 0x80 | 0x40 + 39 and the character, both on E2 only
+    let writes = bus.port_writes();
+    assert_eq!(
+        writes[writes.len() - 8..],
+        [0xE2, 0xE0, 0x72, 0x70, 0x43, 0x41, 0x13, 0x11]
+    );
+}
+
+#[test]
+fn e2_on_the_rw_pin_rules_out_reads() {
+    let mut bus = MockI2c::default();
+    let mut lcd = lcd(&mut bus);
+    lcd.use_busy_flag(true);
+    assert_eq!(lcd.timing_mode(), TimingMode::FixedDelay);
+    assert_eq!(lcd.read_address(), Err(Error::Unsupported));
+
+    // Nothing raised RW, which would have strobed E2
+    assert!(bus.writes.is_empty());
+}
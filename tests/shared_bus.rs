@@ -0,0 +1,41 @@
+mod common;
+
+use core::cell::RefCell;
+
+use common::MockI2c;
+use embedded_hal::i2c::{ErrorKind, ErrorType, I2c, Operation};
+use lcd_i2c::{LcdI2cBlocking, NoDelay, Pcf8574};
+
+// Stand-in for embedded-hal-bus's RefCellDevice: a cheap handle that
+// borrows the bus for each transaction
+struct SharedDevice<'a>(&'a RefCell<MockI2c>);
+
+impl ErrorType for SharedDevice<'_> {
+    type Error = ErrorKind;
+}
+
+impl I2c for SharedDevice<'_> {
+    fn transaction(
+        &mut self,
+        address: u8,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), ErrorKind> {
+        self.0.borrow_mut().transaction(address, operations)
+    }
+}
+
+#[test]
+fn two_displays_share_one_bus() {
+    let bus = RefCell::new(MockI2c::default());
+    let mut top = LcdI2cBlocking::new(Pcf8574::new(SharedDevice(&bus), 0x27), NoDelay);
+    let mut bottom = LcdI2cBlocking::new(Pcf8574::new(SharedDevice(&bus), 0x3F), NoDelay);
+
+    top.clear().unwrap();
+    bottom.clear().unwrap();
+
+    let addresses: Vec<u8> = bus.borrow().writes.iter().map(|(a, _)| *a).collect();
+    assert_eq!(
+        addresses,
+        [0x27; 4].into_iter().chain([0x3F; 4]).collect::<Vec<_>>()
+    );
+}
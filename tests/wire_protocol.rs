@@ -0,0 +1,1514 @@
+mod common;
+
+use std::cell::RefCell;
+use std::convert::Infallible;
+use std::rc::Rc;
+
+use common::{AsyncMock, MockI2c, MockSpi, RecordingDelay, block_on};
+#[cfg(feature = "buffered")]
+use lcd_i2c::FrameBuffer;
+use lcd_i2c::{
+    Animation, AsyncI2c, AsyncSpi, COMMON_ADDRESSES, CgramSlot, Controller, Dimensions, Direction,
+    Error, FnExpander, Font, Interface, LcdConfig, LcdI2c, LcdI2cBlocking, LcdI2cFixed, Lines,
+    Mcp23s08, NoDelay, Pcf8574, Pcf8575, PinMap, PortExpander, RomVariant, Tca9534, TimingMode,
+    charmap, charmap_bits,
+};
+
+// With RS=P0, RW=P1, E=P2, backlight=P3 and D4-D7 on P4-P7, every byte is
+// sent as four port writes: high nibble with E high, then E low, then the
+// same for the low nibble.
+
+#[test]
+fn clear_sends_0x01() {
+    let mut bus = MockI2c::default();
+    let mut lcd = LcdI2cBlocking::new(Pcf8574::new(&mut bus, 0x27), NoDelay);
+    lcd.clear().unwrap();
+
+    assert_eq!(bus.port_writes(), [0x04, 0x00, 0x14, 0x10]);
+}
+
+#[test]
+fn home_sends_0x02() {
+    let mut bus = MockI2c::default();
+    let mut lcd = LcdI2cBlocking::new(Pcf8574::new(&mut bus, 0x27), NoDelay);
+    lcd.home().unwrap();
+
+    assert_eq!(bus.port_writes(), [0x04, 0x00, 0x24, 0x20]);
+}
+
+#[test]
+fn set_cursor_sends_ddram_address() {
+    let mut bus = MockI2c::default();
+    let mut lcd = LcdI2cBlocking::new(Pcf8574::new(&mut bus, 0x27), NoDelay);
+    lcd.set_cursor(5, 1).unwrap();
+
+    // 0x80 | 0x45
+    assert_eq!(bus.port_writes(), [0xC4, 0xC0, 0x54, 0x50]);
+}
+
+#[test]
+fn set_display_control_is_one_command() {
+    let mut bus = MockI2c::default();
+    let mut lcd = LcdI2cBlocking::new(Pcf8574::new(&mut bus, 0x27), NoDelay);
+    lcd.set_display_control(true, false, true).unwrap();
+
+    // 0x08 | display | blink
+    assert_eq!(bus.port_writes(), [0x04, 0x00, 0xD4, 0xD0]);
+}
+
+#[test]
+fn set_entry_mode_is_one_command() {
+    let mut bus = MockI2c::default();
+    let mut lcd = LcdI2cBlocking::new(Pcf8574::new(&mut bus, 0x27), NoDelay);
+    lcd.set_entry_mode(Direction::LeftToRight, true).unwrap();
+
+    // 0x04 | increment | shift
+    assert_eq!(bus.port_writes(), [0x04, 0x00, 0x74, 0x70]);
+}
+
+#[test]
+fn set_text_direction_keeps_autoscroll() {
+    let mut bus = MockI2c::default();
+    let mut lcd = LcdI2cBlocking::new(Pcf8574::new(&mut bus, 0x27), NoDelay);
+    lcd.set_autoscroll(true).unwrap();
+    lcd.set_text_direction(Direction::RightToLeft).unwrap();
+    assert_eq!(lcd.text_direction(), Direction::RightToLeft);
+    assert!(lcd.is_autoscroll());
+
+    assert_eq!(bus.lcd_bytes(), [0x07, 0x05]);
+}
+
+#[test]
+fn set_cursor_rejects_positions_off_screen() {
+    let mut bus = MockI2c::default();
+    let mut lcd = LcdI2cBlocking::new(Pcf8574::new(&mut bus, 0x27), NoDelay);
+    assert_eq!(lcd.set_cursor(0, 2), Err(Error::InvalidCursor));
+    assert_eq!(lcd.set_cursor(16, 0), Err(Error::InvalidCursor));
+
+    assert!(bus.writes.is_empty());
+}
+
+#[test]
+fn write_byte_fast_sends_the_same_bytes() {
+    let mut bus = MockI2c::default();
+    let mut lcd = LcdI2cBlocking::new(Pcf8574::new(&mut bus, 0x27), NoDelay);
+    lcd.write_byte_fast(b'A').unwrap();
+    lcd.write_byte_fast(b'B').unwrap();
+
+    assert_eq!(bus.lcd_bytes(), b"AB");
+}
+
+#[test]
+fn write_byte_sets_rs() {
+    let mut bus = MockI2c::default();
+    let mut lcd = LcdI2cBlocking::new(Pcf8574::new(&mut bus, 0x27), NoDelay);
+    lcd.write_byte(b'A').unwrap();
+
+    assert_eq!(bus.port_writes(), [0x45, 0x41, 0x15, 0x11]);
+}
+
+#[test]
+fn tca9534_writes_the_output_register() {
+    let mut bus = MockI2c::default();
+    let mut lcd = LcdI2cBlocking::new(Tca9534::new(&mut bus, 0x20), NoDelay);
+    lcd.clear().unwrap();
+
+    assert_eq!(
+        bus.writes,
+        [
+            (0x20, vec![0x01, 0x04]),
+            (0x20, vec![0x01, 0x00]),
+            (0x20, vec![0x01, 0x14]),
+            (0x20, vec![0x01, 0x10]),
+        ]
+    );
+}
+
+#[test]
+fn mcp23s08_writes_the_output_latch() {
+    let mut spi = MockSpi::default();
+    let mut lcd = LcdI2cBlocking::new(Mcp23s08::new(&mut spi, 1), NoDelay);
+    lcd.clear().unwrap();
+
+    assert_eq!(
+        spi.writes,
+        [
+            [0x42, 0x0A, 0x04],
+            [0x42, 0x0A, 0x00],
+            [0x42, 0x0A, 0x14],
+            [0x42, 0x0A, 0x10],
+        ]
+    );
+}
+
+#[test]
+fn begin_starts_with_single_0x3_nibbles() {
+    let mut bus = MockI2c::default();
+    let mut lcd = LcdI2cBlocking::new(Pcf8574::new(&mut bus, 0x27), NoDelay);
+    lcd.begin().unwrap();
+
+    // Expander init, then 0x3 three times and 0x2, each as one E pulse
+    assert_eq!(
+        bus.port_writes()[..9],
+        [0x00, 0x34, 0x30, 0x34, 0x30, 0x34, 0x30, 0x24, 0x20]
+    );
+}
+
+#[test]
+fn default_20x4_addresses_the_third_row() {
+    let mut bus = MockI2c::default();
+    let mut lcd = LcdI2cBlocking::default_20x4(&mut bus, NoDelay);
+    lcd.set_cursor(0, 2).unwrap();
+
+    // 0x80 | 0x14
+    assert_eq!(bus.writes[0].0, 0x27);
+    assert_eq!(bus.port_writes(), [0x94, 0x90, 0x44, 0x40]);
+}
+
+#[test]
+fn reset_scroll_undoes_the_tracked_shift() {
+    let mut bus = MockI2c::default();
+    let mut lcd = LcdI2cBlocking::new(Pcf8574::new(&mut bus, 0x27), NoDelay);
+    lcd.scroll_display_left().unwrap();
+    lcd.scroll_display_left().unwrap();
+    assert_eq!(lcd.scroll_offset(), 2);
+    lcd.reset_scroll().unwrap();
+    assert_eq!(lcd.scroll_offset(), 0);
+
+    // Two shifts left (0x18), then two right (0x1C)
+    let writes = bus.port_writes();
+    assert_eq!(
+        writes[8..],
+        [0x14, 0x10, 0xC4, 0xC0, 0x14, 0x10, 0xC4, 0xC0]
+    );
+}
+
+#[test]
+fn scroll_display_shifts_by_a_signed_amount() {
+    let mut bus = MockI2c::default();
+    let mut lcd = LcdI2cBlocking::new(Pcf8574::new(&mut bus, 0x27), NoDelay);
+    lcd.scroll_display(0).unwrap();
+    assert_eq!(lcd.scroll_offset(), 0);
+    lcd.scroll_display(-3).unwrap();
+    assert_eq!(lcd.scroll_offset(), 3);
+    lcd.scroll_display(2).unwrap();
+    assert_eq!(lcd.scroll_offset(), 1);
+    // Past the start of the 40 column line, wrapping around
+    lcd.scroll_display(2).unwrap();
+    assert_eq!(lcd.scroll_offset(), 39);
+
+    // Nothing for 0, three shifts left (0x18), then four right (0x1C)
+    let mut expected = vec![0x18; 3];
+    expected.extend([0x1C; 4]);
+    assert_eq!(bus.lcd_bytes(), expected);
+}
+
+#[test]
+fn custom_row_offsets_override_the_layout() {
+    let mut bus = MockI2c::default();
+    let config = LcdConfig::new()
+        .rows(4)
+        .row_offsets([0x00, 0x40, 0x10, 0x50]);
+    let mut lcd =
+        LcdI2cBlocking::new_with_config(Pcf8574::new(&mut bus, 0x27), config, NoDelay).unwrap();
+    lcd.set_cursor(1, 2).unwrap();
+
+    // 0x80 | 0x11
+    assert_eq!(bus.port_writes(), [0x94, 0x90, 0x14, 0x10]);
+}
+
+#[test]
+fn write_char_sends_one_display_code() {
+    let mut bus = MockI2c::default();
+    let mut lcd = LcdI2cBlocking::new(Pcf8574::new(&mut bus, 0x27), NoDelay);
+    lcd.write_char('¥').unwrap();
+
+    // 0x5C in the A00 ROM, rather than the two UTF-8 bytes
+    assert_eq!(bus.port_writes(), [0x55, 0x51, 0xC5, 0xC1]);
+}
+
+#[test]
+fn write_str_clipped_stops_at_the_row_end() {
+    let mut bus = MockI2c::default();
+    let mut lcd = LcdI2cBlocking::new(Pcf8574::new(&mut bus, 0x27), NoDelay);
+    lcd.left_to_right().unwrap();
+    lcd.set_cursor(13, 0).unwrap();
+
+    assert_eq!(lcd.write_str_clipped("Hello"), Ok(3));
+    assert_eq!(lcd.write_str_clipped("!"), Ok(0));
+}
+
+#[test]
+fn scroll_text_step_wraps_around() {
+    let mut bus = MockI2c::default();
+    let config = LcdConfig::new().cols(4);
+    let mut lcd =
+        LcdI2cBlocking::new_with_config(Pcf8574::new(&mut bus, 0x27), config, NoDelay).unwrap();
+    lcd.left_to_right().unwrap();
+    lcd.scroll_text_step("abcde", 0, 8).unwrap();
+
+    assert_eq!(bus.lcd_bytes()[2..], *b"deab");
+}
+
+#[test]
+fn tca9534_init_uses_the_configured_registers() {
+    let mut bus = MockI2c::default();
+    let expander = Tca9534::new(&mut bus, 0x20)
+        .with_config(0x00)
+        .with_polarity(0x08);
+    let mut lcd = LcdI2cBlocking::new(expander, NoDelay);
+    lcd.begin().unwrap();
+
+    assert_eq!(
+        bus.writes[..2],
+        [(0x20, vec![0x03, 0x00]), (0x20, vec![0x02, 0x08])]
+    );
+}
+
+#[cfg(feature = "buffered")]
+#[test]
+fn update_line_skips_unchanged_rows() {
+    let mut bus = MockI2c::default();
+    let mut lcd = LcdI2cBlocking::new(Pcf8574::new(&mut bus, 0x27), NoDelay);
+    lcd.begin().unwrap();
+
+    assert_eq!(lcd.update_line(1, "21.5C"), Ok(true));
+    assert_eq!(lcd.update_line(1, "21.5C"), Ok(false));
+    assert_eq!(lcd.update_line(1, "21.6C"), Ok(true));
+
+    // Rows start out blank after the clear in `begin`
+    assert_eq!(lcd.update_line(0, ""), Ok(false));
+}
+
+#[test]
+fn eight_bit_interface_sends_whole_bytes() {
+    let mut bus = MockI2c::default();
+    let config = LcdConfig::new().interface(Interface::EightBit);
+    let mut lcd =
+        LcdI2cBlocking::new_with_config(Pcf8575::new(&mut bus, 0x20), config, NoDelay).unwrap();
+    lcd.clear().unwrap();
+
+    // Control port (E on P02), then D0-D7 on port 1
+    assert_eq!(
+        bus.writes,
+        [(0x20, vec![0x04, 0x01]), (0x20, vec![0x00, 0x01])]
+    );
+}
+
+#[test]
+fn eight_bit_interface_needs_a_data_port() {
+    let mut bus = MockI2c::default();
+    let config = LcdConfig::new().interface(Interface::EightBit);
+    let lcd = LcdI2cBlocking::new_with_config(Pcf8574::new(&mut bus, 0x27), config, NoDelay);
+
+    assert!(matches!(lcd, Err(Error::InvalidConfig)));
+}
+
+#[test]
+fn impossible_geometries_are_rejected() {
+    let mut bus = MockI2c::default();
+    for config in [
+        LcdConfig::new().rows(0).cols(0),
+        LcdConfig::new().cols(0),
+        LcdConfig::new().rows(5),
+        LcdConfig::new().dimensions(Dimensions::new(200, 2)),
+        // 120 bytes need a second controller
+        LcdConfig::new().dimensions(Dimensions::new(40, 3)),
+    ] {
+        let lcd = LcdI2cBlocking::new_with_config(Pcf8574::new(&mut bus, 0x27), config, NoDelay);
+        assert!(matches!(lcd, Err(Error::InvalidConfig)));
+    }
+
+    let config = LcdConfig::new().dimensions(Dimensions::new(80, 1));
+    assert!(LcdI2cBlocking::new_with_config(Pcf8574::new(&mut bus, 0x27), config, NoDelay).is_ok());
+}
+
+#[test]
+fn line_modes_must_fit_the_rows_and_font() {
+    let mut bus = MockI2c::default();
+    for config in [
+        // One-line mode only has a single row of DDRAM
+        LcdConfig::new().lines(Lines::One),
+        LcdConfig::new().rows(4).lines(Lines::One),
+        // The 5x10 font needs one-line mode
+        LcdConfig::new().rows(1).font(Font::Dots5x10),
+    ] {
+        let lcd = LcdI2cBlocking::new_with_config(Pcf8574::new(&mut bus, 0x27), config, NoDelay);
+        assert!(matches!(lcd, Err(Error::InvalidConfig)));
+    }
+
+    let config = LcdConfig::new()
+        .rows(1)
+        .lines(Lines::One)
+        .font(Font::Dots5x10);
+    let mut lcd =
+        LcdI2cBlocking::new_with_config(Pcf8574::new(&mut bus, 0x27), config, NoDelay).unwrap();
+    lcd.begin().unwrap();
+
+    // After the expander init and the 4-bit sync: N=0, F=1
+    let writes = bus.port_writes();
+    assert_eq!([writes[9] & 0xF0, writes[11] & 0xF0], [0x20, 0x40]);
+}
+
+#[test]
+fn new_with_size_refuses_to_drive_impossible_geometries() {
+    let mut bus = MockI2c::default();
+    for dimensions in [
+        Dimensions::new(100, 2),
+        Dimensions::new(0, 0),
+        Dimensions::new(16, 0),
+    ] {
+        let mut lcd =
+            LcdI2cBlocking::new_with_size(Pcf8574::new(&mut bus, 0x27), dimensions, NoDelay);
+        assert_eq!(lcd.begin(), Err(Error::InvalidConfig));
+        assert!(matches!(
+            lcd.set_cursor(60, 0),
+            Err(Error::InvalidConfig | Error::InvalidCursor)
+        ));
+    }
+    assert!(bus.writes.is_empty());
+
+    let mut lcd = LcdI2cBlocking::new_with_size(
+        Pcf8574::new(&mut bus, 0x27),
+        Dimensions::new(40, 2),
+        NoDelay,
+    );
+    assert_eq!(lcd.set_cursor(39, 1), Ok(()));
+}
+
+#[test]
+fn row_offsets_must_stay_within_ddram_addresses() {
+    let mut bus = MockI2c::default();
+    let config = LcdConfig::new()
+        .dimensions(Dimensions::new(20, 4))
+        .row_offsets([0x00, 0x40, 0xF8, 0x54]);
+    let lcd = LcdI2cBlocking::new_with_config(Pcf8574::new(&mut bus, 0x27), config, NoDelay);
+    assert!(matches!(lcd, Err(Error::InvalidConfig)));
+
+    // In range, but the end of row 2 would run past 0x7F
+    let config = config.row_offsets([0x00, 0x40, 0x78, 0x54]);
+    let mut lcd =
+        LcdI2cBlocking::new_with_config(Pcf8574::new(&mut bus, 0x27), config, NoDelay).unwrap();
+    lcd.set_cursor(7, 2).unwrap();
+    assert_eq!(lcd.set_cursor(10, 2), Err(Error::InvalidCursor));
+}
+
+#[test]
+fn create_char_addresses_the_slot() {
+    assert_eq!(CgramSlot::new(8), None);
+
+    let mut bus = MockI2c::default();
+    let mut lcd = LcdI2cBlocking::new(Pcf8574::new(&mut bus, 0x27), NoDelay);
+    lcd.create_char(CgramSlot::ALL[3], &[0x1F; 8]).unwrap();
+
+    // 0x40 | 3 << 3, the 8 rows, then back to (0, 0)
+    let bytes = bus.lcd_bytes();
+    assert_eq!(bytes[0], 0x58);
+    assert_eq!(bytes[1..9], [0x1F; 8]);
+    assert_eq!(bytes[9], 0x80);
+}
+
+#[test]
+fn control_lines_hold_steady_across_both_nibbles() {
+    let mut bus = MockI2c::default();
+    let mut lcd = LcdI2cBlocking::new(Pcf8574::new(&mut bus, 0x27), NoDelay);
+    lcd.backlight().unwrap();
+    lcd.set_display_control(true, false, false).unwrap();
+
+    // Backlight (P3) stays on and RS/RW low in all four writes; only E and
+    // the data nibble change. 0x08 | display = 0x0C.
+    assert_eq!(bus.port_writes()[1..], [0x0C, 0x08, 0xCC, 0xC8]);
+}
+
+#[test]
+fn batched_writes_send_the_same_sequence_in_one_transaction() {
+    let mut bus = MockI2c::default();
+    let config = LcdConfig::new().batch_writes(true);
+    let mut lcd =
+        LcdI2cBlocking::new_with_config(Pcf8574::new(&mut bus, 0x27), config, NoDelay).unwrap();
+    lcd.write_byte(b'A').unwrap();
+
+    assert_eq!(bus.writes, [(0x27, vec![0x45, 0x41, 0x15, 0x11])]);
+}
+
+#[test]
+fn ws0010_init_selects_character_mode() {
+    let mut bus = MockI2c::default();
+    let config = LcdConfig::new().controller(Controller::Ws0010);
+    let mut lcd =
+        LcdI2cBlocking::new_with_config(Pcf8574::new(&mut bus, 0x27), config, NoDelay).unwrap();
+    lcd.begin().unwrap();
+    lcd.backlight().unwrap();
+
+    // Expander init, single 0x3 and 0x2 nibbles, function set, mode select
+    let writes = bus.port_writes();
+    assert_eq!(writes[1..5], [0x34, 0x30, 0x24, 0x20]);
+    assert_eq!(
+        writes[5..13],
+        [0x24, 0x20, 0x84, 0x80, 0x14, 0x10, 0x74, 0x70]
+    );
+}
+
+#[test]
+fn ws0010_waits_out_its_clear_and_leaves_the_led_pin_alone() {
+    let mut bus = MockI2c::default();
+    let delay = RecordingDelay::default();
+    let config = LcdConfig::new()
+        .controller(Controller::Ws0010)
+        .backlight(true);
+    let mut lcd =
+        LcdI2cBlocking::new_with_config(Pcf8574::new(&mut bus, 0x27), config, delay.clone())
+            .unwrap();
+    lcd.begin().unwrap();
+    delay.0.borrow_mut().clear();
+    lcd.command(0x01).unwrap();
+
+    // 6.2 ms instead of the HD44780's 1.52 ms
+    assert!(delay.0.borrow().iter().sum::<u32>() >= 6_200_000);
+    drop(lcd);
+    assert!(bus.port_writes().iter().all(|w| w & 0x08 == 0));
+}
+
+#[test]
+fn progress_bar_rounds_to_fifths_of_a_cell() {
+    let mut bus = MockI2c::default();
+    let mut lcd = LcdI2cBlocking::new(Pcf8574::new(&mut bus, 0x27), NoDelay);
+    lcd.left_to_right().unwrap();
+    lcd.progress_bar(0, 1, 4, 55).unwrap();
+
+    // 11 of 20 columns: two full cells, one with a single column, one blank
+    let bytes = bus.lcd_bytes();
+    assert_eq!(bytes[bytes.len() - 5..], [0xC0, 0xFF, 0xFF, 4, b' ']);
+}
+
+#[test]
+fn progress_bar_is_clipped_to_the_row() {
+    let mut bus = MockI2c::default();
+    let mut lcd = LcdI2cBlocking::default_20x4(&mut bus, NoDelay);
+    lcd.left_to_right().unwrap();
+    lcd.progress_bar(15, 0, 10, 100).unwrap();
+    lcd.right_to_left().unwrap();
+    lcd.progress_bar(15, 1, 10, 100).unwrap();
+
+    // Five cells each, starting from the right edge when writing right to
+    // left, and nothing spilling into rows 2 and 3
+    let bytes = bus.lcd_bytes();
+    assert_eq!(
+        bytes[bytes.len() - 13..bytes.len() - 7],
+        [0x80 | 15, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF]
+    );
+    assert_eq!(
+        bytes[bytes.len() - 6..],
+        [0xC0 | 19, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF]
+    );
+}
+
+#[test]
+fn set_address_retargets_later_writes() {
+    let mut bus = MockI2c::default();
+    let mut lcd = LcdI2cBlocking::new(Pcf8574::new(&mut bus, 0x27), NoDelay);
+    lcd.expander_mut().set_address(0x3F);
+    lcd.home().unwrap();
+
+    assert!(bus.writes.iter().all(|(address, _)| *address == 0x3F));
+}
+
+#[test]
+fn write_wrapped_continues_on_the_next_row() {
+    let mut bus = MockI2c::default();
+    let config = LcdConfig::new().cols(4).rows(2);
+    let mut lcd =
+        LcdI2cBlocking::new_with_config(Pcf8574::new(&mut bus, 0x27), config, NoDelay).unwrap();
+    lcd.left_to_right().unwrap();
+    lcd.write_wrapped("abcdefghij").unwrap();
+
+    // Row 1 starts at 0x40; "ij" no longer fits
+    assert_eq!(bus.lcd_bytes()[1..], *b"abcd\xC0efgh");
+}
+
+#[test]
+fn raw_control_lines_are_written_on_commit() {
+    let mut bus = MockI2c::default();
+    let mut lcd = LcdI2cBlocking::new(Pcf8574::new(&mut bus, 0x27), NoDelay);
+    lcd.set_rs(true);
+    lcd.set_enable(true);
+    lcd.set_data(0xA);
+    lcd.commit().unwrap();
+
+    assert_eq!(bus.port_writes(), [0xA5]);
+}
+
+#[test]
+fn debug_shows_the_tracked_state() {
+    let mut bus = MockI2c::default();
+    let mut lcd = LcdI2cBlocking::new(Pcf8574::new(&mut bus, 0x27), NoDelay);
+    lcd.set_cursor(3, 1).unwrap();
+
+    let debug = format!("{lcd:?}");
+    assert!(debug.starts_with("LcdI2c {"));
+    assert!(debug.contains("ddram_address: 67"));
+    assert!(debug.contains("position: (3, 1)"));
+}
+
+#[test]
+fn write_field_pads_out_the_old_value() {
+    let mut bus = MockI2c::default();
+    let mut lcd = LcdI2cBlocking::new(Pcf8574::new(&mut bus, 0x27), NoDelay);
+    lcd.left_to_right().unwrap();
+    lcd.write_field(14, 1, "42", 4).unwrap();
+    lcd.write_field(0, 0, "7", 3).unwrap();
+
+    assert_eq!(
+        bus.lcd_bytes(),
+        [0x06, 0xC0 | 14, b'4', b'2', 0x80, b'7', b' ', b' ']
+    );
+}
+
+#[test]
+fn write_big_digit_programs_its_glyphs_once() {
+    let mut bus = MockI2c::default();
+    let mut lcd = LcdI2cBlocking::new(Pcf8574::new(&mut bus, 0x27), NoDelay);
+    assert_eq!(lcd.write_big_digit(1, 14, 0), Err(Error::InvalidCursor));
+    assert_eq!(lcd.write_big_digit(1, 0, 1), Err(Error::InvalidCursor));
+
+    lcd.write_big_digit(7, 0, 0).unwrap();
+    lcd.write_big_digit(4, 3, 0).unwrap();
+
+    let bytes = bus.lcd_bytes();
+    // Three glyphs, each a CGRAM address, 8 rows and a return to DDRAM
+    assert_eq!(bytes[0], 0x40);
+    assert_eq!(
+        bytes[3 * 10..],
+        [
+            0x80, 0, 0, 0xFF, 0xC0, b' ', b' ', 0xFF, 0x83, 0xFF, 1, 0xFF, 0xC3, b' ', b' ', 0xFF
+        ]
+    );
+}
+
+#[test]
+fn big_digits_and_bars_keep_their_glyphs_when_mixed() {
+    let mut bus = MockI2c::default();
+    let mut lcd = LcdI2cBlocking::new(Pcf8574::new(&mut bus, 0x27), NoDelay);
+    lcd.left_to_right().unwrap();
+    lcd.write_big_digit(8, 0, 0).unwrap();
+    lcd.progress_bar(4, 0, 4, 50).unwrap();
+    lcd.write_big_digit(8, 0, 0).unwrap();
+    lcd.progress_bar(4, 0, 4, 50).unwrap();
+
+    // Each glyph set is loaded once (10 bytes per glyph), then only DDRAM
+    // is written
+    let bytes = bus.lcd_bytes();
+    assert_eq!(bytes.len(), 1 + 3 * 10 + 8 + 4 * 10 + 5 + 8 + 5);
+    assert_eq!(
+        bytes[bytes.len() - 13..],
+        [
+            0x80, 0xFF, 2, 0xFF, 0xC0, 0xFF, 1, 0xFF, 0x84, 0xFF, 0xFF, b' ', b' '
+        ]
+    );
+}
+
+#[test]
+fn begin_reloads_the_built_in_glyphs() {
+    let mut bus = MockI2c::default();
+    let mut lcd = LcdI2cBlocking::new(Pcf8574::new(&mut bus, 0x27), NoDelay);
+    lcd.begin().unwrap();
+    lcd.progress_bar(0, 0, 1, 20).unwrap();
+    lcd.begin().unwrap();
+    lcd.progress_bar(0, 0, 1, 20).unwrap();
+
+    // Set CGRAM address 0x60 followed by the first row of the glyph, 0x10
+    let writes = bus.port_writes();
+    let loads = writes
+        .windows(6)
+        .filter(|w| *w == [0x64, 0x60, 0x04, 0x00, 0x15, 0x11])
+        .count();
+    assert_eq!(loads, 2);
+}
+
+#[test]
+fn full_block_falls_back_to_a_glyph_on_a02() {
+    let mut bus = MockI2c::default();
+    let config = LcdConfig::new().rom(RomVariant::A02);
+    let mut lcd =
+        LcdI2cBlocking::new_with_config(Pcf8574::new(&mut bus, 0x27), config, NoDelay).unwrap();
+    lcd.left_to_right().unwrap();
+    lcd.progress_bar(0, 0, 2, 100).unwrap();
+
+    let bytes = bus.lcd_bytes();
+    // Slot 3 (CGRAM 0x58) after the four partial glyphs
+    assert_eq!(bytes[1 + 4 * 10..1 + 4 * 10 + 2], [0x58, 0x1F]);
+    assert_eq!(bytes[bytes.len() - 3..], [0x80, 3, 3]);
+}
+
+#[test]
+fn resync_4bit_restores_state_without_clearing() {
+    let mut bus = MockI2c::default();
+    let mut lcd = LcdI2cBlocking::new(Pcf8574::new(&mut bus, 0x27), NoDelay);
+    lcd.set_cursor(5, 1).unwrap();
+    let setup = 4;
+    lcd.resync_4bit().unwrap();
+
+    let writes = bus.port_writes();
+    assert_eq!(
+        writes[setup..setup + 8],
+        [0x34, 0x30, 0x34, 0x30, 0x34, 0x30, 0x24, 0x20]
+    );
+    // Function set, display control, entry mode and the cursor, no clear
+    assert_eq!(bus.lcd_bytes()[setup / 4 + 2..], [0x28, 0x08, 0x06, 0xC5]);
+}
+
+#[test]
+fn fn_expander_passes_every_port_state_to_the_closure() {
+    let mut ports = Vec::new();
+    let expander = FnExpander::new(|value| {
+        ports.push(value);
+        Ok::<_, Infallible>(())
+    });
+    let mut lcd = LcdI2cBlocking::new(expander, NoDelay);
+    lcd.write_byte(b'A').unwrap();
+
+    assert_eq!(ports, [0x45, 0x41, 0x15, 0x11]);
+}
+
+#[test]
+fn fn_expander_cannot_be_read() {
+    let expander = FnExpander::new(|_| Ok::<_, Infallible>(()));
+    let mut lcd = LcdI2cBlocking::new(expander, NoDelay);
+    lcd.use_busy_flag(true);
+
+    assert_eq!(lcd.read_address(), Err(Error::Unsupported));
+    assert_eq!(lcd.read_byte(), Err(Error::Unsupported));
+    assert_eq!(lcd.timing_mode(), TimingMode::FixedDelay);
+}
+
+#[test]
+fn charmap_builds_glyphs_from_pixel_rows() {
+    const HEART: [u8; 8] = charmap([
+        ".....", ".#.#.", "#####", "#####", ".###.", "..#..", ".....", ".....",
+    ]);
+    assert_eq!(HEART, [0x00, 0x0A, 0x1F, 0x1F, 0x0E, 0x04, 0x00, 0x00]);
+    assert_eq!(charmap_bits([0b11111; 8]), [0x1F; 8]);
+}
+
+#[test]
+#[should_panic]
+fn charmap_bits_rejects_more_than_5_bits() {
+    charmap_bits([0x20, 0, 0, 0, 0, 0, 0, 0]);
+}
+
+#[test]
+fn write_str_at_reports_what_fit_on_the_row() {
+    let mut bus = MockI2c::default();
+    let mut lcd = LcdI2cBlocking::default_20x4(&mut bus, NoDelay);
+    lcd.left_to_right().unwrap();
+    assert_eq!(lcd.write_str_at(17, 0, "Hello").unwrap(), 3);
+    assert_eq!(lcd.position(), (0, 2));
+
+    assert_eq!(bus.lcd_bytes(), [0x06, 0x80 | 17, b'H', b'e', b'l']);
+}
+
+#[test]
+fn resume_restores_the_state_from_before_suspend() {
+    let mut bus = MockI2c::default();
+    let mut lcd = LcdI2cBlocking::new(Pcf8574::new(&mut bus, 0x27), NoDelay);
+    lcd.set_display_control(true, true, false).unwrap();
+    lcd.backlight().unwrap();
+
+    lcd.suspend().unwrap();
+    assert!(!lcd.is_display_on());
+    lcd.resume().unwrap();
+    assert!(lcd.is_display_on() && lcd.is_cursor_on() && !lcd.is_blink_on());
+
+    let writes = bus.port_writes();
+    // Display off with the backlight still on, then the backlight off
+    assert_eq!(writes[5..10], [0x0C, 0x08, 0xAC, 0xA8, 0x00]);
+    // Display back on while dark, then the backlight
+    assert_eq!(writes[10..], [0x04, 0x00, 0xE4, 0xE0, 0x08]);
+}
+
+#[test]
+fn set_cgram_leaves_the_cursor_to_the_caller() {
+    let mut bus = MockI2c::default();
+    let mut lcd = LcdI2cBlocking::new(Pcf8574::new(&mut bus, 0x27), NoDelay);
+    lcd.set_cgram(CgramSlot::ALL[0], &[0x1F; 8]).unwrap();
+    lcd.set_cgram(CgramSlot::ALL[1], &[0x11; 8]).unwrap();
+    lcd.set_cursor(0, 1).unwrap();
+
+    let bytes = bus.lcd_bytes();
+    assert_eq!(bytes.len(), 2 * 9 + 1);
+    assert_eq!([bytes[0], bytes[9], bytes[18]], [0x40, 0x48, 0xC0]);
+}
+
+#[cfg(feature = "stats")]
+#[test]
+fn transactions_counts_bus_transactions() {
+    let mut bus = MockI2c::default();
+    let mut lcd = LcdI2cBlocking::new(Pcf8574::new(&mut bus, 0x27), NoDelay);
+    lcd.write_byte(b'A').unwrap();
+    assert_eq!(lcd.transactions(), 4);
+
+    lcd.reset_transactions();
+    lcd.set_backlight(true).unwrap();
+    assert_eq!(lcd.transactions(), 1);
+
+    // Two nibbles of E high, read, E low, then RW back low. The PCF8574
+    // has no data direction to switch.
+    lcd.reset_transactions();
+    lcd.read_address().unwrap();
+    assert_eq!(lcd.transactions(), 7);
+    assert_eq!(bus.transactions, 4 + 1 + 7);
+
+    // The TCA9534 cannot batch, and switches D4-D7 around the read
+    let mut bus = MockI2c::default();
+    let config = LcdConfig::new().batch_writes(true);
+    let mut lcd =
+        LcdI2cBlocking::new_with_config(Tca9534::new(&mut bus, 0x20), config, NoDelay).unwrap();
+    lcd.write_byte(b'A').unwrap();
+    assert_eq!(lcd.transactions(), 4);
+    lcd.reset_transactions();
+    lcd.read_address().unwrap();
+    assert_eq!(lcd.transactions(), 9);
+}
+
+#[test]
+fn positioned_helpers_fill_their_cells_right_to_left() {
+    let mut bus = MockI2c::default();
+    let mut lcd = LcdI2cBlocking::new(Pcf8574::new(&mut bus, 0x27), NoDelay);
+    lcd.right_to_left().unwrap();
+    lcd.write_field(4, 0, "ab", 3).unwrap();
+    lcd.write_big_digit(7, 10, 0).unwrap();
+
+    let bytes = bus.lcd_bytes();
+    // Starts at the right end of the field so the padding ends up on the left
+    assert_eq!(bytes[..5], [0x04, 0x80 | 6, b'a', b'b', b' ']);
+    // Drawn from the right, the digit still reads T T F / _ _ F
+    assert_eq!(
+        bytes[bytes.len() - 8..],
+        [0x80 | 12, 0xFF, 0, 0, 0xC0 | 12, 0xFF, b' ', b' ']
+    );
+}
+
+#[test]
+fn row_writers_fill_the_row_right_to_left() {
+    let mut bus = MockI2c::default();
+    let config = LcdConfig::new().cols(4);
+    let mut lcd =
+        LcdI2cBlocking::new_with_config(Pcf8574::new(&mut bus, 0x27), config, NoDelay).unwrap();
+    lcd.right_to_left().unwrap();
+    lcd.clear_line(1).unwrap();
+    lcd.scroll_text_step("abcde", 1, 8).unwrap();
+
+    // Every write starts at column 3 and walks down to column 0
+    assert_eq!(
+        bus.lcd_bytes(),
+        [
+            0x04,
+            0xC0 | 3,
+            b' ',
+            b' ',
+            b' ',
+            b' ',
+            0xC0 | 3,
+            0xC0 | 3,
+            b'd',
+            b'e',
+            b'a',
+            b'b',
+        ]
+    );
+}
+
+#[cfg(feature = "buffered")]
+#[test]
+fn frame_buffer_flushes_right_to_left_runs_from_their_right_end() {
+    let mut bus = MockI2c::default();
+    let mut lcd = LcdI2c::new(Pcf8574::new(&mut bus, 0x27), NoDelay);
+    block_on(lcd.right_to_left()).unwrap();
+    let mut frame = FrameBuffer::new(Dimensions::new(4, 1));
+    frame.write_str_at(0, 0, "abcd");
+    block_on(frame.flush(&mut lcd)).unwrap();
+
+    assert_eq!(bus.lcd_bytes(), [0x04, 0x80 | 3, b'd', b'c', b'b', b'a']);
+}
+
+#[test]
+fn new_checked_rejects_shifted_addresses() {
+    let mut bus = MockI2c::default();
+    assert!(Pcf8574::new_checked(&mut bus, 0x4E).is_none());
+    assert!(Pcf8574::new_checked(&mut bus, 0x7E).is_none());
+    assert!(Pcf8575::new_checked(&mut bus, 0x40).is_none());
+    assert!(Tca9534::new_checked(&mut bus, 0x40).is_none());
+
+    let expander = Pcf8574::new_checked(&mut bus, 0x3F).unwrap();
+    assert_eq!(expander.address(), 0x3F);
+}
+
+#[test]
+fn write_screen_pads_and_blanks_every_row() {
+    let mut bus = MockI2c::default();
+    let config = LcdConfig::new().dimensions(Dimensions::new(4, 2));
+    let mut lcd =
+        LcdI2cBlocking::new_with_config(Pcf8574::new(&mut bus, 0x27), config, NoDelay).unwrap();
+    lcd.left_to_right().unwrap();
+    lcd.write_screen(&["Temperature"]).unwrap();
+    lcd.write_screen(&["a", "b", "c"]).unwrap();
+
+    assert_eq!(bus.lcd_bytes()[1..], *b"\x80Temp\xC0    \x80a   \xC0b   ");
+}
+
+#[test]
+fn broken_pin_maps_are_rejected() {
+    assert!(PinMap::DEFAULT.is_valid() && PinMap::DATA_LOW.is_valid());
+    let shared_rw = PinMap {
+        e2: Some(1),
+        ..PinMap::DEFAULT
+    };
+    assert!(shared_rw.is_valid());
+
+    let mut bus = MockI2c::default();
+    for pins in [
+        PinMap {
+            led: 8,
+            ..PinMap::DEFAULT
+        },
+        PinMap {
+            data: 5,
+            ..PinMap::DEFAULT
+        },
+        // RS on D4
+        PinMap {
+            rs: 4,
+            ..PinMap::DEFAULT
+        },
+        PinMap {
+            e2: Some(2),
+            ..PinMap::DEFAULT
+        },
+    ] {
+        let expander = Pcf8574::new(&mut bus, 0x27).with_pin_map(pins);
+        let lcd = LcdI2cBlocking::new_with_config(expander, LcdConfig::new(), NoDelay);
+        assert!(matches!(lcd, Err(Error::InvalidConfig)));
+    }
+}
+
+#[test]
+fn unchecked_constructors_refuse_to_drive_a_broken_pin_map() {
+    let led_past_the_port = PinMap {
+        led: 8,
+        ..PinMap::DEFAULT
+    };
+    let mut bus = MockI2c::default();
+    let expander = Pcf8574::new(&mut bus, 0x27).with_pin_map(led_past_the_port);
+    let mut lcd = LcdI2cBlocking::new(expander, NoDelay);
+    assert_eq!(lcd.begin(), Err(Error::InvalidConfig));
+    assert_eq!(lcd.write_byte(b'A'), Err(Error::InvalidConfig));
+    assert_eq!(lcd.backlight(), Err(Error::InvalidConfig));
+    assert_eq!(lcd.ping(), Err(Error::InvalidConfig));
+
+    let expander = Pcf8574::new(&mut bus, 0x27).with_pin_map(led_past_the_port);
+    let mut lcd = LcdI2cFixed::<_, _, 16, 2>::new(expander, NoDelay);
+    assert_eq!(block_on(lcd.begin()), Err(Error::InvalidConfig));
+
+    assert!(bus.writes.is_empty());
+}
+
+#[test]
+fn pin_map_moves_data_to_the_low_nibble() {
+    let mut bus = MockI2c::default();
+    let expander = Pcf8574::new(&mut bus, 0x27).with_pin_map(PinMap::DATA_LOW);
+    let mut lcd = LcdI2cBlocking::new(expander, NoDelay);
+    lcd.write_byte(b'A').unwrap();
+
+    // 'A' = 0x41 with RS=P4 and E=P6
+    assert_eq!(bus.port_writes(), [0x54, 0x14, 0x51, 0x11]);
+}
+
+#[test]
+fn blink_tick_toggles_only_the_cursor() {
+    let mut bus = MockI2c::default();
+    let mut lcd = LcdI2cBlocking::new(Pcf8574::new(&mut bus, 0x27), NoDelay);
+    lcd.display().unwrap();
+    lcd.blink_tick().unwrap();
+    assert!(lcd.is_cursor_on() && !lcd.is_blink_on());
+    lcd.blink_tick().unwrap();
+    assert!(!lcd.is_cursor_on() && lcd.is_display_on());
+
+    assert_eq!(bus.lcd_bytes(), [0x0C, 0x0E, 0x0C]);
+}
+
+#[cfg(feature = "buffered")]
+#[test]
+fn frame_buffer_encodes_for_the_character_rom() {
+    let mut bus = MockI2c::default();
+    let mut lcd = LcdI2c::new_with_size(
+        Pcf8574::new(&mut bus, 0x27),
+        Dimensions::new(16, 2),
+        NoDelay,
+    );
+    let mut frame = FrameBuffer::new(Dimensions::new(16, 2)).with_rom(RomVariant::A02);
+    frame.write_str_at(0, 0, "é°");
+    block_on(frame.flush(&mut lcd)).unwrap();
+
+    // Two cells, not the four UTF-8 bytes
+    let bytes = bus.lcd_bytes();
+    assert_eq!(bytes[..3], [0x80, 0xE9, 0xB0]);
+    assert_eq!(bytes[3..17], [b' '; 14]);
+}
+
+#[cfg(feature = "buffered")]
+#[test]
+fn blocking_driver_flushes_frame_buffers() {
+    let mut bus = MockI2c::default();
+    let config = LcdConfig::new().cols(4).rows(1);
+    let mut lcd =
+        LcdI2cBlocking::new_with_config(Pcf8574::new(&mut bus, 0x27), config, NoDelay).unwrap();
+    let mut frame = FrameBuffer::new(lcd.dimensions());
+    frame.write_str_at(0, 0, "ab");
+    lcd.flush_frame(&mut frame).unwrap();
+    frame.write_str_at(3, 0, "c");
+    lcd.flush_frame(&mut frame).unwrap();
+
+    assert_eq!(bus.lcd_bytes(), [0x80, b'a', b'b', b' ', b' ', 0x83, b'c']);
+}
+
+#[test]
+fn debug_output_tells_the_bus_and_ddram_addresses_apart() {
+    let mut bus = MockI2c::default();
+    let mut lcd = LcdI2cBlocking::new(Pcf8574::new(&mut bus, 0x27), NoDelay);
+    lcd.set_cursor(3, 1).unwrap();
+
+    let debug = format!("{lcd:?}");
+    assert!(debug.contains("bus_address: Some(39)"), "{debug}");
+    assert!(debug.contains("ddram_address: 67"), "{debug}");
+
+    let lcd = LcdI2cBlocking::new(FnExpander::new(|_| Ok::<_, Infallible>(())), NoDelay);
+    assert!(format!("{lcd:?}").contains("bus_address: None"));
+}
+
+#[test]
+fn numbers_are_written_as_decimal_digits() {
+    let mut bus = MockI2c::default();
+    let mut lcd = LcdI2cBlocking::new(Pcf8574::new(&mut bus, 0x27), NoDelay);
+    lcd.write_u32(0).unwrap();
+    lcd.write_u32(u32::MAX).unwrap();
+    lcd.write_i32(i32::MIN).unwrap();
+    lcd.write_i32(-7).unwrap();
+
+    assert_eq!(bus.lcd_bytes(), b"04294967295-2147483648-7");
+}
+
+#[test]
+fn padded_numbers_are_right_aligned() {
+    let mut bus = MockI2c::default();
+    let mut lcd = LcdI2cBlocking::new(Pcf8574::new(&mut bus, 0x27), NoDelay);
+    lcd.write_u32_padded(42, 5, b' ').unwrap();
+    lcd.write_u32_padded(0, 3, b'0').unwrap();
+    // Wider than the field: written in full
+    lcd.write_u32_padded(12345, 2, b' ').unwrap();
+
+    assert_eq!(bus.lcd_bytes(), b"   4200012345");
+}
+
+#[test]
+fn reset_reinitializes_and_restores_the_modes() {
+    let mut bus = MockI2c::default();
+    let mut lcd = LcdI2cBlocking::new(Pcf8574::new(&mut bus, 0x27), NoDelay);
+    lcd.begin().unwrap();
+    lcd.cursor().unwrap();
+    lcd.autoscroll().unwrap();
+    lcd.reset().unwrap();
+
+    // Expander init and the 4-bit sync, run a second time by `reset`
+    let sync = [0x00, 0x34, 0x30, 0x34, 0x30, 0x34, 0x30, 0x24, 0x20];
+    let writes = bus.port_writes();
+    let starts: Vec<usize> = (0..writes.len())
+        .filter(|&i| writes[i..].starts_with(&sync))
+        .collect();
+    assert_eq!(starts.len(), 2);
+    let writes = &writes[starts[1]..];
+    // Function set, display off, clear and entry mode, then display control
+    // (the extra 0x0C comes from the init itself) and entry mode restored
+    // with the cursor and autoscroll on, and the backlight port state
+    let bytes: Vec<u8> = writes[9..writes.len() - 1]
+        .chunks(4)
+        .map(|w| (w[0] & 0xF0) | (w[2] >> 4))
+        .collect();
+    assert_eq!(bytes, [0x28, 0x08, 0x01, 0x07, 0x0C, 0x0E, 0x07]);
+    assert_eq!(writes[writes.len() - 1], 0x00);
+}
+
+#[test]
+fn cursor_moves_are_single_shift_instructions() {
+    let mut bus = MockI2c::default();
+    let mut lcd = LcdI2cBlocking::new(Pcf8574::new(&mut bus, 0x27), NoDelay);
+    lcd.set_cursor(5, 1).unwrap();
+    lcd.move_cursor_right().unwrap();
+    assert_eq!(lcd.position(), (6, 1));
+    lcd.move_cursor_left().unwrap();
+    lcd.move_cursor_left().unwrap();
+    assert_eq!(lcd.position(), (4, 1));
+
+    // Cursor shift right (0x14) and left (0x10), no set_cursor in between
+    assert_eq!(bus.lcd_bytes(), [0xC5, 0x14, 0x10, 0x10]);
+}
+
+#[test]
+fn create_char_can_return_to_the_cursor() {
+    let glyph = [0xFF, 0x11, 0, 0, 0, 0, 0, 0x1F];
+
+    // Back to (0, 0) by default, with the rows sent as given
+    let mut bus = MockI2c::default();
+    let mut lcd = LcdI2cBlocking::new(Pcf8574::new(&mut bus, 0x27), NoDelay);
+    lcd.set_cursor(3, 1).unwrap();
+    lcd.create_char(CgramSlot::ALL[1], &glyph).unwrap();
+    let bytes = bus.lcd_bytes();
+    assert_eq!(bytes[..2], [0xC3, 0x48]);
+    assert_eq!(bytes[2..10], glyph);
+    assert_eq!(bytes[10..], [0x80]);
+
+    // Back to (3, 1), with the top 3 bits of every row cleared
+    let mut bus = MockI2c::default();
+    let config = LcdConfig::new().preserve_cursor(true).mask_charmap(true);
+    let mut lcd =
+        LcdI2cBlocking::new_with_config(Pcf8574::new(&mut bus, 0x27), config, NoDelay).unwrap();
+    lcd.set_cursor(3, 1).unwrap();
+    lcd.create_char(CgramSlot::ALL[1], &glyph).unwrap();
+    assert_eq!(lcd.position(), (3, 1));
+    let bytes = bus.lcd_bytes();
+    assert_eq!(bytes[2..10], [0x1F, 0x11, 0, 0, 0, 0, 0, 0x1F]);
+    assert_eq!(bytes[10..], [0xC3]);
+}
+
+#[test]
+fn text_is_translated_for_the_character_rom() {
+    let text = "aé°ü~\\€";
+
+    // A00: ° and ü have their own codes, é, ~ and \ do not exist, and the
+    // katakana half of the ROM is reachable from Unicode
+    let mut bus = MockI2c::default();
+    let mut lcd = LcdI2cBlocking::new(Pcf8574::new(&mut bus, 0x27), NoDelay);
+    lcd.write_str(text).unwrap();
+    lcd.write_str("ｱ").unwrap();
+    assert_eq!(
+        bus.lcd_bytes(),
+        [b'a', b'?', 0xDF, 0xF5, b'?', b'?', b'?', 0xB1]
+    );
+
+    // A02 follows Latin-1, and has ASCII in full
+    let mut bus = MockI2c::default();
+    let config = LcdConfig::new().rom(RomVariant::A02);
+    let mut lcd =
+        LcdI2cBlocking::new_with_config(Pcf8574::new(&mut bus, 0x27), config, NoDelay).unwrap();
+    lcd.write_str(text).unwrap();
+    assert_eq!(bus.lcd_bytes(), [b'a', 0xE9, 0xB0, 0xFC, b'~', b'\\', b'?']);
+}
+
+#[test]
+fn animation_reprograms_its_slot_and_rewrites_the_cell() {
+    const FRAMES: [[u8; 8]; 2] = [[0x01; 8], [0x02; 8]];
+    let mut bus = MockI2c::default();
+    let mut lcd = LcdI2c::new(Pcf8574::new(&mut bus, 0x27), NoDelay);
+    block_on(lcd.left_to_right()).unwrap();
+    let mut spinner = Animation::new(&FRAMES, CgramSlot::ALL[5], 7, 1);
+    for _ in 0..3 {
+        block_on(spinner.step(&mut lcd)).unwrap();
+    }
+    assert_eq!(spinner.frame(), 1);
+    assert_eq!(lcd.position(), (8, 1));
+
+    // Each step: CGRAM slot 5 (0x68), the frame, the cell and slot code 5
+    let bytes = bus.lcd_bytes();
+    let steps: Vec<&[u8]> = bytes[1..].chunks(11).collect();
+    assert_eq!(steps.len(), 3);
+    for (step, frame) in steps.iter().zip([0x01, 0x02, 0x01]) {
+        assert_eq!(step[0], 0x68);
+        assert_eq!(step[1..9], [frame; 8]);
+        assert_eq!(step[9..], [0xC7, 5]);
+    }
+}
+
+#[test]
+fn blocking_driver_steps_animations() {
+    const FRAMES: [[u8; 8]; 2] = [[0x04; 8], [0x08; 8]];
+    let mut bus = MockI2c::default();
+    let mut lcd = LcdI2cBlocking::new(Pcf8574::new(&mut bus, 0x27), NoDelay);
+    let mut spinner = Animation::new(&FRAMES, CgramSlot::ALL[6], 0, 0);
+    lcd.step_animation(&mut spinner).unwrap();
+    lcd.step_animation(&mut spinner).unwrap();
+    assert_eq!(spinner.frame(), 0);
+
+    let bytes = bus.lcd_bytes();
+    assert_eq!(bytes[..11], [0x70, 4, 4, 4, 4, 4, 4, 4, 4, 0x80, 6]);
+    assert_eq!(bytes[11..], [0x70, 8, 8, 8, 8, 8, 8, 8, 8, 0x80, 6]);
+}
+
+#[test]
+fn fixed_geometry_checks_the_cursor_at_compile_time() {
+    type Lcd2004<'a> = LcdI2cFixed<Pcf8574<&'a mut MockI2c>, NoDelay, 20, 4>;
+    assert_eq!(Lcd2004::DIMENSIONS, Dimensions::new(20, 4));
+
+    let mut bus = MockI2c::default();
+    let mut lcd = Lcd2004::new(Pcf8574::new(&mut bus, 0x27), NoDelay);
+    block_on(lcd.set_cursor_at::<19, 3>()).unwrap();
+    // The runtime driver, through Deref
+    assert_eq!(lcd.dimensions(), Dimensions::new(20, 4));
+    assert_eq!(block_on(lcd.set_cursor(20, 0)), Err(Error::InvalidCursor));
+
+    // 0x80 | (0x54 + 19)
+    assert_eq!(bus.lcd_bytes(), [0xE7]);
+}
+
+#[test]
+fn active_low_backlight_inverts_the_led_bit() {
+    let mut bus = MockI2c::default();
+    let config = LcdConfig::new().backlight_active_low(true);
+    let mut lcd =
+        LcdI2cBlocking::new_with_config(Pcf8574::new(&mut bus, 0x27), config, NoDelay).unwrap();
+    lcd.no_backlight().unwrap();
+    lcd.backlight().unwrap();
+    lcd.write_byte(b'A').unwrap();
+
+    // Off drives P3 high, on pulls it low, and data writes keep it low
+    assert_eq!(bus.port_writes(), [0x08, 0x00, 0x45, 0x41, 0x15, 0x11]);
+}
+
+#[test]
+fn init_keeps_an_active_low_backlight_off() {
+    let mut bus = MockI2c::default();
+    let config = LcdConfig::new().backlight(false).backlight_active_low(true);
+    let mut lcd =
+        LcdI2cBlocking::new_with_config(Pcf8574::new(&mut bus, 0x27), config, NoDelay).unwrap();
+    lcd.begin().unwrap();
+
+    // P3 stays high from the very first write on
+    let writes = bus.port_writes();
+    assert_eq!(writes[0], 0x08);
+    assert!(writes.iter().all(|port| port & 0x08 != 0), "{writes:x?}");
+
+    let mut bus = MockI2c::default();
+    let config = LcdConfig::new().backlight(false).backlight_active_low(true);
+    let expander = Tca9534::new(&mut bus, 0x20);
+    let mut lcd = LcdI2cBlocking::new_with_config(expander, config, NoDelay).unwrap();
+    lcd.begin().unwrap();
+
+    assert_eq!(bus.writes[2], (0x20, vec![0x01, 0x08]));
+}
+
+// Backlight on a PWM channel of its own, recording every level
+struct PwmBacklight(Vec<u8>);
+
+impl PortExpander for PwmBacklight {
+    type Error = Infallible;
+
+    async fn write_port(&mut self, _value: u8) -> Result<(), Infallible> {
+        Ok(())
+    }
+
+    async fn read_port(&mut self) -> Result<u8, Infallible> {
+        Ok(0)
+    }
+
+    fn has_backlight_pwm(&self) -> bool {
+        true
+    }
+
+    async fn set_backlight_pwm(&mut self, level: u8) -> Result<(), Infallible> {
+        self.0.push(level);
+        Ok(())
+    }
+}
+
+#[test]
+fn backlight_level_is_on_off_without_pwm() {
+    let mut bus = MockI2c::default();
+    let mut lcd = LcdI2cBlocking::new(Pcf8574::new(&mut bus, 0x27), NoDelay);
+    lcd.set_backlight_level(128).unwrap();
+    lcd.set_backlight_level(0).unwrap();
+    lcd.set_backlight_level(1).unwrap();
+
+    assert_eq!(bus.port_writes(), [0x08, 0x00, 0x08]);
+}
+
+#[test]
+fn backlight_level_is_passed_to_a_pwm_expander() {
+    let mut lcd = LcdI2cBlocking::new(PwmBacklight(Vec::new()), NoDelay);
+    lcd.set_backlight_level(128).unwrap();
+    lcd.set_backlight_level(0).unwrap();
+
+    assert_eq!(lcd.expander().0, [128, 0]);
+}
+
+#[test]
+fn backlight_on_off_goes_through_the_pwm_channel() {
+    let mut lcd = LcdI2cBlocking::new(PwmBacklight(Vec::new()), NoDelay);
+    lcd.no_backlight().unwrap();
+    lcd.backlight().unwrap();
+    lcd.set_backlight(false).unwrap();
+
+    assert_eq!(lcd.expander().0, [0, 255, 0]);
+}
+
+#[test]
+fn resume_restores_a_dimmed_pwm_backlight() {
+    let mut lcd = LcdI2cBlocking::new(PwmBacklight(Vec::new()), NoDelay);
+    lcd.set_backlight_level(96).unwrap();
+    lcd.suspend().unwrap();
+    lcd.resume().unwrap();
+
+    assert_eq!(lcd.expander().0, [96, 0, 96]);
+}
+
+#[cfg(feature = "fmt")]
+#[test]
+fn write_macro_formats_through_fmt_write() {
+    use core::fmt::Write;
+
+    let mut bus = MockI2c::default();
+    let mut lcd = LcdI2cBlocking::new(Pcf8574::new(&mut bus, 0x27), NoDelay);
+    write!(lcd, "{}", 42).unwrap();
+    write!(lcd, "{:>4}", -7).unwrap();
+
+    assert_eq!(bus.lcd_bytes(), *b"42  -7");
+}
+
+#[cfg(feature = "fmt")]
+#[test]
+fn fmt_char_delay_follows_every_formatted_character() {
+    use core::fmt::Write;
+
+    let mut bus = MockI2c::default();
+    let delay = RecordingDelay::default();
+    let config = LcdConfig::new().fmt_char_delay_us(500);
+    let mut lcd =
+        LcdI2cBlocking::new_with_config(Pcf8574::new(&mut bus, 0x27), config, delay.clone())
+            .unwrap();
+    write!(lcd, "ab").unwrap();
+
+    // Enable pulse, nibble settle, enable pulse and the write's own 41us,
+    // then the extra wait
+    let per_char = [1_000, 37_000, 1_000, 41_000, 500_000];
+    assert_eq!(*delay.0.borrow(), [per_char, per_char].concat());
+
+    // Only `fmt::Write` waits the extra time
+    delay.0.borrow_mut().clear();
+    lcd.write_str("ab").unwrap();
+    assert!(!delay.0.borrow().contains(&500_000));
+}
+
+#[test]
+fn clear_line_blanks_the_row_and_returns_to_its_start() {
+    let mut bus = MockI2c::default();
+    let config = LcdConfig::new().cols(8);
+    let mut lcd =
+        LcdI2cBlocking::new_with_config(Pcf8574::new(&mut bus, 0x27), config, NoDelay).unwrap();
+    lcd.clear_line(1).unwrap();
+    assert_eq!(lcd.position(), (0, 1));
+
+    let mut expected = vec![0xC0];
+    expected.extend([b' '; 8]);
+    expected.push(0xC0);
+    assert_eq!(bus.lcd_bytes(), expected);
+
+    // Right to left the spaces walk down from the last column
+    let mut bus = MockI2c::default();
+    let mut lcd =
+        LcdI2cBlocking::new_with_config(Pcf8574::new(&mut bus, 0x27), config, NoDelay).unwrap();
+    lcd.right_to_left().unwrap();
+    lcd.clear_line(0).unwrap();
+    assert_eq!(lcd.position(), (7, 0));
+
+    let mut expected = vec![0x04, 0x80 | 7];
+    expected.extend([b' '; 8]);
+    expected.push(0x80 | 7);
+    assert_eq!(bus.lcd_bytes(), expected);
+}
+
+#[test]
+fn newline_and_carriage_return_move_the_cursor() {
+    let mut bus = MockI2c::default();
+    let mut lcd = LcdI2cBlocking::new(Pcf8574::new(&mut bus, 0x27), NoDelay);
+    lcd.write_str("ab\rc").unwrap();
+    assert_eq!(lcd.position(), (1, 0));
+    lcd.write_str("\nd").unwrap();
+    assert_eq!(lcd.position(), (1, 1));
+    // Past the last row, '\n' wraps back to row 0
+    lcd.write_str("\ne").unwrap();
+    assert_eq!(lcd.position(), (1, 0));
+
+    assert_eq!(
+        bus.lcd_bytes(),
+        [b'a', b'b', 0x80, b'c', 0xC0, b'd', 0x80, b'e']
+    );
+}
+
+#[test]
+fn blocking_detect_returns_the_first_address_that_acks() {
+    // 0x27 and 0x3F do not answer, 0x20 does
+    let mut bus = MockI2c {
+        nacks: 2,
+        ..Default::default()
+    };
+    assert_eq!(
+        lcd_i2c::blocking::detect(&mut bus, &COMMON_ADDRESSES),
+        Some(0x20)
+    );
+    assert_eq!(bus.transactions, 3);
+
+    let mut bus = MockI2c {
+        nacks: COMMON_ADDRESSES.len(),
+        ..Default::default()
+    };
+    assert_eq!(lcd_i2c::blocking::detect(&mut bus, &COMMON_ADDRESSES), None);
+    assert_eq!(bus.transactions, COMMON_ADDRESSES.len());
+    assert!(bus.writes.is_empty());
+}
+
+#[test]
+fn async_i2c_sends_the_same_port_writes() {
+    let mut bus = MockI2c::default();
+    // The blocking wrapper keeps polling a bus that returns `Pending`
+    let mut lcd = LcdI2cBlocking::new(Pcf8574::new(AsyncI2c(AsyncMock(&mut bus)), 0x27), NoDelay);
+    lcd.clear().unwrap();
+    lcd.write_byte(b'A').unwrap();
+
+    assert_eq!(
+        bus.port_writes(),
+        [0x04, 0x00, 0x14, 0x10, 0x45, 0x41, 0x15, 0x11]
+    );
+    assert!(bus.writes.iter().all(|(address, _)| *address == 0x27));
+}
+
+#[test]
+fn async_spi_sends_the_same_latch_writes() {
+    let mut spi = MockSpi::default();
+    let mut lcd = LcdI2cBlocking::new(Mcp23s08::new(AsyncSpi(AsyncMock(&mut spi)), 1), NoDelay);
+    lcd.clear().unwrap();
+
+    assert_eq!(
+        spi.writes,
+        [
+            [0x42, 0x0A, 0x04],
+            [0x42, 0x0A, 0x00],
+            [0x42, 0x0A, 0x14],
+            [0x42, 0x0A, 0x10],
+        ]
+    );
+}
+
+#[test]
+fn setup_and_enable_pulse_times_frame_each_nibble() {
+    let mut bus = MockI2c::default();
+    let delay = RecordingDelay::default();
+    let config = LcdConfig::new().setup_us(5).enable_pulse_us(3);
+    let mut lcd =
+        LcdI2cBlocking::new_with_config(Pcf8574::new(&mut bus, 0x27), config, delay.clone())
+            .unwrap();
+    lcd.write_byte(b'A').unwrap();
+
+    // Setup, high pulse, nibble settle, low pulse, then the write's 41us
+    assert_eq!(*delay.0.borrow(), [5_000, 3_000, 37_000, 3_000, 41_000]);
+    // The setup time gets a port write of its own with RS up and E low
+    assert_eq!(bus.port_writes(), [0x41, 0x45, 0x41, 0x15, 0x11]);
+}
+
+#[test]
+fn nibble_settle_time_separates_the_two_nibbles() {
+    let mut bus = MockI2c::default();
+    let delay = RecordingDelay::default();
+    let config = LcdConfig::new().nibble_settle_us(10);
+    let mut lcd =
+        LcdI2cBlocking::new_with_config(Pcf8574::new(&mut bus, 0x27), config, delay.clone())
+            .unwrap();
+    lcd.write_byte(b'A').unwrap();
+
+    // High pulse, settle, low pulse, then the write's 41us
+    assert_eq!(*delay.0.borrow(), [1_000, 10_000, 1_000, 41_000]);
+}
+
+#[test]
+fn write_byte_fast_skips_the_post_write_delay() {
+    let mut bus = MockI2c::default();
+    let delay = RecordingDelay::default();
+    let mut lcd = LcdI2cBlocking::new(Pcf8574::new(&mut bus, 0x27), delay.clone());
+    lcd.write_byte_fast(b'A').unwrap();
+    lcd.write_byte_fast(b'B').unwrap();
+
+    // Only the pulses and the nibble settle time, no 41us per byte
+    let per_byte = [1_000, 37_000, 1_000];
+    assert_eq!(*delay.0.borrow(), [per_byte, per_byte].concat());
+    assert_eq!(bus.lcd_bytes(), *b"AB");
+}
+
+// Port writes and delays in the order the driver issued them
+#[derive(Debug, PartialEq)]
+enum Step {
+    Port(u8),
+    Delay(u32),
+}
+
+#[derive(Clone)]
+struct TimelineDelay(Rc<RefCell<Vec<Step>>>);
+
+impl embedded_hal::delay::DelayNs for TimelineDelay {
+    fn delay_ns(&mut self, ns: u32) {
+        self.0.borrow_mut().push(Step::Delay(ns));
+    }
+}
+
+#[test]
+fn power_on_delay_comes_before_the_first_bus_access() {
+    let timeline = Rc::new(RefCell::new(Vec::new()));
+    let ports = timeline.clone();
+    let expander = FnExpander::new(move |value| {
+        ports.borrow_mut().push(Step::Port(value));
+        Ok::<_, Infallible>(())
+    });
+    let config = LcdConfig::new().power_on_delay_ms(120);
+    let mut lcd =
+        LcdI2cBlocking::new_with_config(expander, config, TimelineDelay(timeline.clone())).unwrap();
+
+    lcd.begin().unwrap();
+    assert_eq!(
+        timeline.borrow()[..2],
+        [Step::Delay(120_000_000), Step::Port(0x00)]
+    );
+
+    timeline.borrow_mut().clear();
+    lcd.reset().unwrap();
+    assert_eq!(
+        timeline.borrow()[..2],
+        [Step::Delay(120_000_000), Step::Port(0x00)]
+    );
+}
+
+#[test]
+fn blink_at_and_stop_editing_only_send_the_needed_commands() {
+    let mut bus = MockI2c::default();
+    let mut lcd = LcdI2cBlocking::new(Pcf8574::new(&mut bus, 0x27), NoDelay);
+    lcd.display().unwrap();
+    lcd.blink_at(3, 1).unwrap();
+    // Blink is already on, so only the cursor moves
+    lcd.blink_at(5, 0).unwrap();
+    lcd.cursor().unwrap();
+    lcd.stop_editing().unwrap();
+    // Nothing left to hide
+    lcd.stop_editing().unwrap();
+
+    assert_eq!(
+        bus.lcd_bytes(),
+        [0x0C, 0xC0 | 3, 0x0D, 0x80 | 5, 0x0F, 0x0C]
+    );
+}
+
+#[test]
+fn write_bytes_sends_raw_display_codes() {
+    let mut bus = MockI2c::default();
+    let mut lcd = LcdI2cBlocking::new(Pcf8574::new(&mut bus, 0x27), NoDelay);
+    lcd.set_cursor(2, 1).unwrap();
+    // Custom glyph codes and bytes above 0x7F go out untranslated
+    lcd.write_bytes(&[0x00, b'x', 0x07, 0xFF]).unwrap();
+    lcd.write_bytes(&[]).unwrap();
+    assert_eq!(lcd.position(), (6, 1));
+
+    assert_eq!(bus.lcd_bytes(), [0xC2, 0x00, b'x', 0x07, 0xFF]);
+}